@@ -4,11 +4,17 @@ struct HealthbarMaterial {
     filled_color: vec4<f32>,
     empty_color: vec4<f32>,
     fraction: f32,
+    shield_color: vec4<f32>,
+    shield_fraction: f32,
 }
 
 @group(1) @binding(0) var<uniform> material: HealthbarMaterial;
 
 @fragment
 fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    // The shield is drawn as a stripe along the top of the bar
+    if mesh.uv[1] <= 0.35 && mesh.uv[0] <= material.shield_fraction {
+        return material.shield_color;
+    }
     return select(material.empty_color, material.filled_color, mesh.uv[0] <= material.fraction);
 }
\ No newline at end of file
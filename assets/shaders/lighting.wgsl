@@ -0,0 +1,55 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// Array sizes have to match `MAX_LIGHTS` and `MAX_WALLS` in lighting.rs
+struct LightingMaterial {
+    ambient: vec4<f32>,
+    light_colors: array<vec4<f32>, 32>,
+    // Each light is its position in xy, its radius in z, and 1 in w if it casts shadows
+    lights: array<vec4<f32>, 32>,
+    light_count: u32,
+    // Each wall is min.xy and max.xy of its bounding box
+    walls: array<vec4<f32>, 16>,
+    wall_count: u32,
+}
+
+@group(1) @binding(0) var<uniform> material: LightingMaterial;
+
+// Whether a wall lies between `start` and `end`
+fn occluded(start: vec2<f32>, end: vec2<f32>) -> bool {
+    let ray = end - start;
+    // Keeps rays running straight along an axis from dividing by zero
+    let direction = select(ray, vec2(1e-6), abs(ray) < vec2(1e-6));
+    for (var i = 0u; i < material.wall_count; i++) {
+        let wall = material.walls[i];
+        let t1 = (wall.xy - start) / direction;
+        let t2 = (wall.zw - start) / direction;
+        let t_near = max(min(t1.x, t2.x), min(t1.y, t2.y));
+        let t_far = min(max(t1.x, t2.x), max(t1.y, t2.y));
+        // Only counts when the ray leaves the wall before reaching the pixel, so walls themselves
+        // stay lit and only what's behind them is in shadow
+        if t_near <= t_far && t_near > 0.0 && t_far < 1.0 {
+            return true;
+        }
+    }
+    return false;
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let position = mesh.world_position.xy;
+    var light = material.ambient.rgb;
+    for (var i = 0u; i < material.light_count; i++) {
+        let source = material.lights[i];
+        let distance = length(position - source.xy);
+        if distance >= source.z {
+            continue;
+        }
+        if source.w > 0.5 && occluded(source.xy, position) {
+            continue;
+        }
+        let falloff = 1.0 - distance / source.z;
+        light += material.light_colors[i].rgb * falloff * falloff;
+    }
+    // Multiplied with what's already been drawn, see `LightingMaterial::specialize`
+    return vec4(min(light, vec3(1.0)), 1.0);
+}
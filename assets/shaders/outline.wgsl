@@ -0,0 +1,47 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+struct OutlineMaterial {
+    color: vec4<f32>,
+    // The frame's rect in the atlas, in pixels, as min.xy and max.xy
+    frame: vec4<f32>,
+    // How thick the outline is, in pixels of the frame
+    width: f32,
+    flip_x: u32,
+}
+
+@group(1) @binding(0) var<uniform> material: OutlineMaterial;
+@group(1) @binding(1) var atlas: texture_2d<f32>;
+
+// Whether the frame has an opaque pixel at `pixel`, counting everything outside the frame as empty
+fn opaque(pixel: vec2<i32>) -> bool {
+    let size = vec2<i32>(material.frame.zw - material.frame.xy);
+    if any(pixel < vec2(0)) || any(pixel >= size) {
+        return false;
+    }
+    return textureLoad(atlas, vec2<i32>(material.frame.xy) + pixel, 0).a > 0.5;
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    var uv = mesh.uv;
+    if material.flip_x != 0u {
+        uv.x = 1.0 - uv.x;
+    }
+    // The quad sticks out `width` pixels past the frame on every side, to leave room for the outline
+    let size = material.frame.zw - material.frame.xy;
+    let pixel = vec2<i32>(floor(uv * (size + 2.0 * material.width) - material.width));
+    // The sprite itself shows through, only the empty pixels around it get drawn
+    if opaque(pixel) {
+        discard;
+    }
+    let reach = i32(ceil(material.width));
+    for (var y = -reach; y <= reach; y++) {
+        for (var x = -reach; x <= reach; x++) {
+            if f32(x * x + y * y) <= material.width * material.width
+                && opaque(pixel + vec2(x, y)) {
+                return material.color;
+            }
+        }
+    }
+    discard;
+}
@@ -0,0 +1,289 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{AsBindGroup, ShaderRef},
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemy::Enemy,
+    healthbar::{BarKind, HealthbarMaterial},
+    localization::Localization,
+    states::AppState,
+};
+
+/// Bright yellow stands out against both the dark map and the pale ghosts
+const OUTLINE_COLOR: Color = Color::rgb(1.0, 0.9, 0.0);
+/// How thick enemy outlines are, in pixels of the enemy's sprite
+const OUTLINE_WIDTH: f32 = 3.0;
+/// How far above its enemy an outline is drawn, in world units: over the lighting overlay, but
+/// under health bars
+const OUTLINE_Z: f32 = 0.9;
+
+/// Colors for bars and other things that are otherwise told apart by color alone
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    /// Protanopia and deuteranopia, which both make red and green hard to tell apart
+    RedGreen,
+    /// Tritanopia
+    BlueYellow,
+}
+
+impl ColorPalette {
+    pub const ALL: [ColorPalette; 3] = [
+        ColorPalette::Standard,
+        ColorPalette::RedGreen,
+        ColorPalette::BlueYellow,
+    ];
+
+    pub fn name<'a>(&self, localization: &'a Localization) -> &'a str {
+        localization.get(match self {
+            ColorPalette::Standard => "palette.standard",
+            ColorPalette::RedGreen => "palette.red_green",
+            ColorPalette::BlueYellow => "palette.blue_yellow",
+        })
+    }
+
+    /// The filled and empty colors of a bar
+    pub fn bar_colors(&self, kind: BarKind) -> (Color, Color) {
+        let empty = match kind {
+            BarKind::EnemyHealth => Color::DARK_GRAY,
+            _ => Color::GRAY,
+        };
+        // The colorblind palettes are picked from Okabe and Ito's
+        let filled = match (self, kind) {
+            (ColorPalette::Standard, BarKind::PlayerHealth | BarKind::EnemyHealth) => Color::RED,
+            (ColorPalette::Standard, BarKind::Experience) => Color::LIME_GREEN,
+            (ColorPalette::Standard, BarKind::Dash) => Color::CYAN,
+            (ColorPalette::RedGreen, BarKind::PlayerHealth | BarKind::EnemyHealth) => {
+                Color::rgb(0.84, 0.37, 0.0)
+            }
+            (ColorPalette::RedGreen, BarKind::Experience) => Color::rgb(0.0, 0.45, 0.7),
+            (ColorPalette::RedGreen, BarKind::Dash) => Color::rgb(0.94, 0.89, 0.26),
+            (ColorPalette::BlueYellow, BarKind::PlayerHealth | BarKind::EnemyHealth) => {
+                Color::rgb(0.9, 0.1, 0.1)
+            }
+            (ColorPalette::BlueYellow, BarKind::Experience) => Color::rgb(0.0, 0.62, 0.45),
+            (ColorPalette::BlueYellow, BarKind::Dash) => Color::rgb(0.8, 0.47, 0.65),
+        };
+        (filled, empty)
+    }
+
+    /// Color of the shield stripe drawn over health bars
+    pub fn shield_color(&self) -> Color {
+        match self {
+            ColorPalette::Standard => Color::rgb(0.4, 0.7, 1.0),
+            ColorPalette::RedGreen => Color::rgb(0.34, 0.71, 0.91),
+            ColorPalette::BlueYellow => Color::rgb(0.9, 0.9, 0.9),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub palette: ColorPalette,
+    /// Multiplies the size of menus and UI text, on top of fitting them to the window
+    pub ui_scale: f32,
+    /// Turns off screen shake and flashing sprites
+    pub reduced_motion: bool,
+    /// Draws a bright outline around enemies so they stand out from the map
+    #[serde(alias = "enemy_highlights")]
+    pub enemy_outlines: bool,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        AccessibilitySettings {
+            palette: ColorPalette::Standard,
+            ui_scale: 1.0,
+            reduced_motion: false,
+            enemy_outlines: false,
+        }
+    }
+}
+
+fn apply_palette(
+    settings: Res<AccessibilitySettings>,
+    mut materials: ResMut<Assets<HealthbarMaterial>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (_, material) in materials.iter_mut() {
+        material.recolor(settings.palette);
+    }
+}
+
+/// Draws a solid line around the opaque pixels of one frame of a texture atlas
+#[derive(Asset, TypePath, AsBindGroup, Debug, Default, Clone, PartialEq)]
+pub struct OutlineMaterial {
+    #[uniform(0)]
+    color: Color,
+    /// The frame's rect in the atlas, in pixels, as min.xy and max.xy
+    #[uniform(0)]
+    frame: Vec4,
+    /// How thick the outline is, in pixels of the frame
+    #[uniform(0)]
+    width: f32,
+    /// 1 if the frame is mirrored, like `TextureAtlasSprite::flip_x`
+    #[uniform(0)]
+    flip_x: u32,
+    #[texture(1)]
+    atlas: Handle<Image>,
+}
+
+impl Material2d for OutlineMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/outline.wgsl".into()
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct OutlineAssets {
+    mesh: Mesh2dHandle,
+}
+
+fn load_outline_assets(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let mesh = meshes.add(shape::Quad::new(Vec2::ONE).into()).into();
+    commands.insert_resource(OutlineAssets { mesh });
+}
+
+#[derive(Component, Debug)]
+struct EnemyOutline;
+
+#[derive(Component, Debug)]
+struct HasOutline;
+
+/// The outline for `sprite`'s current frame, and the size of the quad that fits around it
+fn outline_for(
+    sprite: &TextureAtlasSprite,
+    texture_atlas: &TextureAtlas,
+) -> Option<(OutlineMaterial, Vec2)> {
+    let rect = texture_atlas.textures.get(sprite.index)?;
+    let material = OutlineMaterial {
+        color: OUTLINE_COLOR.with_a(sprite.color.a()),
+        frame: Vec4::new(rect.min.x, rect.min.y, rect.max.x, rect.max.y),
+        width: OUTLINE_WIDTH,
+        flip_x: sprite.flip_x as u32,
+        atlas: texture_atlas.texture.clone(),
+    };
+    // Sprites are a unit per pixel before their entity's scale
+    Some((material, rect.size() + 2.0 * OUTLINE_WIDTH))
+}
+
+fn add_enemy_outlines(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &TextureAtlasSprite,
+            &Handle<TextureAtlas>,
+        ),
+        (With<Enemy>, Without<HasOutline>),
+    >,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    mut materials: ResMut<Assets<OutlineMaterial>>,
+    outline_assets: Res<OutlineAssets>,
+    settings: Res<AccessibilitySettings>,
+) {
+    if !settings.enemy_outlines {
+        return;
+    }
+    for (e, enemy_transform, sprite, texture_atlas) in query.iter() {
+        // Tried again next frame if the atlas isn't built yet
+        let Some((material, size)) = texture_atlases
+            .get(texture_atlas)
+            .and_then(|texture_atlas| outline_for(sprite, texture_atlas))
+        else {
+            continue;
+        };
+        // The enemy's scale applies to this too, so divide it back out
+        let transform = Transform::from_xyz(0.0, 0.0, OUTLINE_Z / enemy_transform.scale.z)
+            .with_scale(size.extend(1.0));
+
+        let outline = commands
+            .spawn((
+                MaterialMesh2dBundle {
+                    mesh: outline_assets.mesh.clone(),
+                    material: materials.add(material),
+                    transform,
+                    ..Default::default()
+                },
+                EnemyOutline,
+            ))
+            .id();
+        commands.entity(e).insert(HasOutline).add_child(outline);
+    }
+}
+
+fn remove_enemy_outlines(
+    mut commands: Commands,
+    outline_query: Query<Entity, With<EnemyOutline>>,
+    enemy_query: Query<Entity, With<HasOutline>>,
+    settings: Res<AccessibilitySettings>,
+) {
+    if settings.enemy_outlines || !settings.is_changed() {
+        return;
+    }
+    for e in outline_query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    for e in enemy_query.iter() {
+        commands.entity(e).remove::<HasOutline>();
+    }
+}
+
+/// Keeps outlines on the same animation frame as their enemy
+fn sync_enemy_outlines(
+    mut outline_query: Query<
+        (&Parent, &Handle<OutlineMaterial>, &mut Transform),
+        With<EnemyOutline>,
+    >,
+    enemy_query: Query<(&TextureAtlasSprite, &Handle<TextureAtlas>)>,
+    texture_atlases: Res<Assets<TextureAtlas>>,
+    mut materials: ResMut<Assets<OutlineMaterial>>,
+) {
+    for (parent, material, mut transform) in outline_query.iter_mut() {
+        let Ok((sprite, texture_atlas)) = enemy_query.get(parent.get()) else {
+            continue;
+        };
+        let Some((outline, size)) = texture_atlases
+            .get(texture_atlas)
+            .and_then(|texture_atlas| outline_for(sprite, texture_atlas))
+        else {
+            continue;
+        };
+        if transform.scale.truncate() != size {
+            transform.scale = size.extend(1.0);
+        }
+        // Touching the material uploads it again, so only do it when the frame changed
+        if materials.get(material) != Some(&outline) {
+            materials.insert(material, outline);
+        }
+    }
+}
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<OutlineMaterial>::default())
+            .init_resource::<AccessibilitySettings>()
+            .add_systems(Startup, load_outline_assets)
+            .add_systems(Update, apply_palette)
+            .add_systems(
+                Update,
+                (
+                    remove_enemy_outlines,
+                    add_enemy_outlines,
+                    sync_enemy_outlines,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
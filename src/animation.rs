@@ -0,0 +1,276 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    character::Character,
+    health::{DamageTaken, Health},
+    states::AppState,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Facing {
+    #[default]
+    Right,
+    Up,
+    Left,
+    Down,
+}
+
+impl Facing {
+    /// Picks whichever of the four directions is closest to `dir`
+    pub fn from_direction(dir: Vec2) -> Self {
+        let angle = Vec2::X.angle_between(dir);
+        if angle < 0.25 * PI && angle > -0.25 * PI {
+            Facing::Right
+        } else if (0.25 * PI..0.75 * PI).contains(&angle) {
+            Facing::Up
+        } else if angle <= -0.25 * PI && angle > -0.75 * PI {
+            Facing::Down
+        } else {
+            Facing::Left
+        }
+    }
+
+    /// Like `from_direction`, but only ever picks left or right
+    pub fn from_direction_horizontal(dir: Vec2) -> Self {
+        if dir.x <= 0.0 {
+            Facing::Left
+        } else {
+            Facing::Right
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Clip {
+    Idle(Facing),
+    Walk(Facing),
+    Hit,
+    Death,
+}
+
+impl Default for Clip {
+    fn default() -> Self {
+        Clip::Idle(Facing::default())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    /// Indices into the texture atlas
+    pub frames: Vec<usize>,
+    /// Seconds per frame at normal playback speed
+    pub frame_duration: f32,
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<usize>, frame_duration: f32, looping: bool) -> Self {
+        AnimationClip {
+            frames,
+            frame_duration,
+            looping,
+        }
+    }
+}
+
+/// All the clips for one kind of character, along with the atlas their frames come from
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct AnimationSet {
+    pub atlas: Handle<TextureAtlas>,
+    pub clips: HashMap<Clip, AnimationClip>,
+}
+
+impl AnimationSet {
+    /// Returns the requested clip, falling back to something sensible if this set doesn't have it
+    pub fn get(&self, clip: Clip) -> Option<&AnimationClip> {
+        if let Some(c) = self.clips.get(&clip) {
+            return Some(c);
+        }
+        match clip {
+            Clip::Walk(facing) => self.clips.get(&Clip::Idle(facing)),
+            _ => None,
+        }
+    }
+}
+
+/// Packs separately loaded frames into a single texture atlas, or logs why it couldn't
+///
+/// Every image must already be loaded. The atlas index of each frame can be looked up with
+/// `frame_index`.
+pub fn build_atlas(frames: &[Handle<Image>], textures: &mut Assets<Image>) -> Option<TextureAtlas> {
+    let mut builder = TextureAtlasBuilder::default();
+    for frame in frames {
+        let Some(image) = textures.get(frame) else {
+            error!("Tried to build an atlas from an image that isn't loaded");
+            continue;
+        };
+        builder.add_texture(frame.id(), image);
+    }
+    match builder.finish(textures) {
+        Ok(atlas) => Some(atlas),
+        Err(err) => {
+            error!("Couldn't pack animation frames into an atlas: {err}");
+            None
+        }
+    }
+}
+
+/// Index of `frame` in an atlas from `build_atlas`, falling back to the first frame if it's missing
+pub fn frame_index(atlas: &TextureAtlas, frame: &Handle<Image>) -> usize {
+    atlas.get_texture_index(frame).unwrap_or_else(|| {
+        warn!(
+            "Animation frame {:?} is missing from its atlas",
+            frame.path()
+        );
+        0
+    })
+}
+
+#[derive(Component, Debug, Default)]
+pub struct Animator {
+    pub set: Handle<AnimationSet>,
+    pub facing: Facing,
+    current: Clip,
+    /// A clip that plays once and then goes back to whatever the character would normally be doing
+    one_shot: Option<Clip>,
+    frame: usize,
+    timer: f32,
+    speed: f32,
+}
+
+impl Animator {
+    pub fn new(set: Handle<AnimationSet>) -> Self {
+        Animator {
+            set,
+            speed: 1.0,
+            ..Default::default()
+        }
+    }
+
+    pub fn play_once(&mut self, clip: Clip) {
+        self.one_shot = Some(clip);
+    }
+
+    fn switch_to(&mut self, clip: Clip) {
+        if self.current != clip {
+            self.current = clip;
+            self.frame = 0;
+            self.timer = 0.0;
+        }
+    }
+}
+
+fn choose_clips(
+    mut query: Query<(
+        &mut Animator,
+        Option<&Velocity>,
+        Option<&Character>,
+        Option<&Health>,
+    )>,
+) {
+    // Below this speed characters are considered to be standing still
+    const WALK_THRESHOLD: f32 = 0.2;
+
+    for (mut animator, velocity, character, health) in query.iter_mut() {
+        let speed = velocity.map(|v| v.linvel.length()).unwrap_or(0.0);
+        let facing = animator.facing;
+
+        if health.is_some_and(|h| h.dead) {
+            animator.switch_to(Clip::Death);
+            animator.speed = 1.0;
+        } else if let Some(clip) = animator.one_shot {
+            animator.switch_to(clip);
+            animator.speed = 1.0;
+        } else if speed > WALK_THRESHOLD {
+            animator.switch_to(Clip::Walk(facing));
+            // Walk cycles speed up and slow down with the character
+            let max_speed = character.map(|c| c.max_speed).unwrap_or(speed);
+            animator.speed = if max_speed > 0.0 {
+                (speed / max_speed).clamp(0.25, 2.0)
+            } else {
+                1.0
+            };
+        } else {
+            animator.switch_to(Clip::Idle(facing));
+            animator.speed = 1.0;
+        }
+    }
+}
+
+fn advance_animations(
+    mut query: Query<(
+        &mut Animator,
+        &mut TextureAtlasSprite,
+        &mut Handle<TextureAtlas>,
+    )>,
+    animation_sets: Res<Assets<AnimationSet>>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (mut animator, mut sprite, mut atlas) in query.iter_mut() {
+        let Some(set) = animation_sets.get(&animator.set) else {
+            continue;
+        };
+        if *atlas != set.atlas {
+            *atlas = set.atlas.clone();
+        }
+        let Some(clip) = set.get(animator.current) else {
+            // Nothing to play for this clip, so don't let a one-shot get stuck
+            if animator.one_shot == Some(animator.current) {
+                animator.one_shot = None;
+            }
+            continue;
+        };
+        // A clip without a positive frame duration would never finish a frame
+        if clip.frames.is_empty() || clip.frame_duration <= 0.0 {
+            continue;
+        }
+
+        animator.timer += dt * animator.speed;
+        while animator.timer >= clip.frame_duration {
+            animator.timer -= clip.frame_duration;
+            animator.frame += 1;
+
+            if animator.frame >= clip.frames.len() {
+                if clip.looping {
+                    animator.frame = 0;
+                } else {
+                    animator.frame = clip.frames.len() - 1;
+                    if animator.one_shot == Some(animator.current) {
+                        animator.one_shot = None;
+                    }
+                    break;
+                }
+            }
+        }
+
+        let index = clip.frames[animator.frame.min(clip.frames.len() - 1)];
+        if sprite.index != index {
+            sprite.index = index;
+        }
+    }
+}
+
+fn play_hit_animations(mut query: Query<&mut Animator>, mut reader: EventReader<DamageTaken>) {
+    for ev in reader.read() {
+        if let Ok(mut animator) = query.get_mut(ev.entity) {
+            animator.play_once(Clip::Hit);
+        }
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AnimationSet>().add_systems(
+            Update,
+            (play_hit_animations, choose_clips, advance_animations)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
@@ -1,113 +1,475 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, LoadState},
+    audio::{Decodable, PlaybackMode, Source},
+    prelude::*,
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+    utils::{BoxedFuture, HashMap},
+};
 use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 
-use crate::{loading::LoadingAssets, states::AppState};
+use crate::{enemy::BigGhost, loading::LoadingAssets, states::AppState};
+
+/// How long it takes for one track to fade into another, in seconds
+const CROSSFADE_TIME: f32 = 2.0;
+/// Number of enemies at which intensity layers play at full volume
+const FULL_INTENSITY_ENEMIES: f32 = 60.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistTrack {
+    pub path: String,
+    /// Played alongside `path`, louder the more enemies there are
+    pub layer: Option<String>,
+}
+
+/// A list of tracks for each kind of music, loaded from a `.playlist` file
+#[derive(Asset, TypePath, Debug, Default)]
+pub struct Playlist {
+    pub menu: Vec<PlaylistTrack>,
+    pub game: Vec<PlaylistTrack>,
+    pub boss: Vec<PlaylistTrack>,
+}
+
+#[derive(Default)]
+struct PlaylistLoader;
+
+impl AssetLoader for PlaylistLoader {
+    type Asset = Playlist;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+
+            let dir = load_context
+                .path()
+                .parent()
+                .unwrap_or(std::path::Path::new(""));
+            let resolve = |path: &str| dir.join(path.trim()).to_string_lossy().into_owned();
+
+            let mut playlist = Playlist::default();
+            let mut section = None;
+            for line in text.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                    section = match name {
+                        "menu" => Some(&mut playlist.menu),
+                        "game" => Some(&mut playlist.game),
+                        "boss" => Some(&mut playlist.boss),
+                        _ => {
+                            warn!("Unknown playlist section [{name}], ignoring it");
+                            None
+                        }
+                    };
+                    continue;
+                }
+                let Some(tracks) = section.as_mut() else {
+                    continue;
+                };
+
+                let (path, layer) = match line.split_once('|') {
+                    Some((path, layer)) => (path, Some(resolve(layer))),
+                    None => (line, None),
+                };
+                tracks.push(PlaylistTrack {
+                    path: resolve(path),
+                    layer,
+                });
+            }
+
+            Ok(playlist)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["playlist"]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum MusicContext {
+    #[default]
+    Menu,
+    Game,
+    /// A big ghost is around
+    Boss,
+}
+
+#[derive(Debug, Clone)]
+struct TrackAssets {
+    track: Handle<AudioSource>,
+    layer: Option<Handle<AudioSource>>,
+}
 
 #[derive(Debug, Default, Resource)]
 struct MusicAssets {
-    tracks: Vec<Handle<AudioSource>>,
+    playlist: Handle<Playlist>,
+    menu: Vec<TrackAssets>,
+    game: Vec<TrackAssets>,
+    boss: Vec<TrackAssets>,
+}
+
+impl MusicAssets {
+    /// Returns the tracks for `context`, using the in-game tracks if there aren't any
+    fn tracks(&self, context: MusicContext) -> &[TrackAssets] {
+        let tracks = match context {
+            MusicContext::Menu => &self.menu,
+            MusicContext::Game => &self.game,
+            MusicContext::Boss => &self.boss,
+        };
+        if tracks.is_empty() {
+            &self.game
+        } else {
+            tracks
+        }
+    }
 }
 
-fn load_music(
+fn load_playlist(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut loading_assets: ResMut<LoadingAssets>,
 ) {
-    let mut tracks = vec![
-        asset_server.load::<AudioSource>("music/Alpha Hydrae - La Peche.mp3"),
-        asset_server.load::<AudioSource>("music/Zoliborz - To Balagopalan Ganapathy.mp3"),
-        asset_server.load::<AudioSource>("music/Alpha Hydrae - To be like a chased rabbit.mp3"),
-        asset_server.load::<AudioSource>("music/Cathedral Of Chemical Equilibrium - One.mp3"),
-        asset_server.load::<AudioSource>("music/Monplaisir - This is not a joke.mp3"),
-    ];
+    let playlist = asset_server.load::<Playlist>("music/default.playlist");
+    loading_assets.add(playlist.clone());
+
+    commands.insert_resource(MusicAssets {
+        playlist,
+        ..Default::default()
+    });
+}
 
-    tracks.shuffle(&mut rand::thread_rng());
+/// Starts loading every track in the playlist.
+///
+/// The tracks themselves aren't waited on by the loading screen, so any that are missing just get
+/// skipped instead of holding up the game.
+fn load_tracks(
+    mut music_assets: ResMut<MusicAssets>,
+    playlists: Res<Assets<Playlist>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(playlist) = playlists.get(&music_assets.playlist) else {
+        warn!("Couldn't load the music playlist, there won't be any music");
+        return;
+    };
 
-    for track in tracks.iter() {
-        loading_assets.add(track.clone());
+    let load = |tracks: &[PlaylistTrack]| {
+        let mut tracks: Vec<TrackAssets> = tracks
+            .iter()
+            .map(|t| TrackAssets {
+                track: asset_server.load(&t.path),
+                layer: t.layer.as_ref().map(|layer| asset_server.load(layer)),
+            })
+            .collect();
+        tracks.shuffle(&mut rand::thread_rng());
+        tracks
+    };
+
+    music_assets.menu = load(&playlist.menu);
+    music_assets.game = load(&playlist.game);
+    music_assets.boss = load(&playlist.boss);
+}
+
+fn update_music_context(
+    mut context: ResMut<MusicContext>,
+    state: Res<State<AppState>>,
+    boss_query: Query<(), With<BigGhost>>,
+) {
+    let new_context = match state.get() {
+        AppState::InGame | AppState::Restart if !boss_query.is_empty() => MusicContext::Boss,
+        AppState::InGame | AppState::Restart => MusicContext::Game,
+        AppState::Loading | AppState::MainMenu | AppState::Dead => MusicContext::Menu,
+    };
+    if *context != new_context {
+        *context = new_context;
     }
+}
 
-    commands.insert_resource(MusicAssets { tracks });
+/// How hard the game is going right now, between 0 and 1. Drives the volume of intensity layers.
+#[derive(Debug, Default, Resource)]
+pub struct MusicIntensity(pub f32);
+
+fn update_music_intensity(
+    mut intensity: ResMut<MusicIntensity>,
+    enemy_query: Query<(), With<crate::enemy::Enemy>>,
+    time: Res<Time<Real>>,
+) {
+    let target = (enemy_query.iter().count() as f32 / FULL_INTENSITY_ENEMIES).min(1.0);
+    // Ease towards the target so the music doesn't jump around every time something dies
+    let t = 1.0 - 0.3_f32.powf(time.delta_seconds());
+    intensity.0 += (target - intensity.0) * t;
+}
+
+/// How long each loaded track is, in seconds.
+///
+/// Decoders don't know that up front for compressed formats, so each track gets decoded once in
+/// the background to count its samples.
+#[derive(Debug, Default, Resource)]
+struct TrackDurations {
+    known: HashMap<AssetId<AudioSource>, f32>,
+    measuring: HashMap<AssetId<AudioSource>, Task<f32>>,
+}
+
+fn measure_tracks(
+    mut durations: ResMut<TrackDurations>,
+    music_assets: Res<MusicAssets>,
+    sources: Res<Assets<AudioSource>>,
+) {
+    let TrackDurations { known, measuring } = &mut *durations;
+    let tracks = music_assets
+        .menu
+        .iter()
+        .chain(&music_assets.game)
+        .chain(&music_assets.boss);
+    for track in tracks {
+        let id = track.track.id();
+        if known.contains_key(&id) || measuring.contains_key(&id) {
+            continue;
+        }
+        let Some(source) = sources.get(id).cloned() else {
+            continue;
+        };
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let decoder = source.decoder();
+            let samples_per_second = decoder.channels() as f32 * decoder.sample_rate() as f32;
+            decoder.count() as f32 / samples_per_second
+        });
+        measuring.insert(id, task);
+    }
+
+    measuring.retain(|id, task| {
+        if !task.is_finished() {
+            return true;
+        }
+        known.insert(*id, block_on(task));
+        false
+    });
 }
 
 #[derive(Component, Debug, Default)]
+struct MusicTrack {
+    gain: f32,
+    fading_out: bool,
+    is_layer: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PlayingTrack {
+    track: Entity,
+    layer: Option<Entity>,
+    source: AssetId<AudioSource>,
+    /// When the track started, in real seconds since startup
+    started: f32,
+}
+
+#[derive(Debug, Default, Resource)]
 struct MusicPlayer {
-    track: Option<usize>,
+    context: Option<MusicContext>,
+    /// Index into the current context's tracks
+    position: usize,
+    current: Option<PlayingTrack>,
+}
+
+impl MusicPlayer {
+    fn fade_out_current(&mut self, query: &mut Query<&mut MusicTrack>) {
+        let Some(current) = self.current.take() else {
+            return;
+        };
+        for e in [Some(current.track), current.layer].into_iter().flatten() {
+            if let Ok(mut track) = query.get_mut(e) {
+                track.fading_out = true;
+            }
+        }
+    }
 }
 
-fn setup_music(mut commands: Commands) {
-    commands.spawn((
-        AudioBundle {
-            settings: PlaybackSettings {
-                mode: bevy::audio::PlaybackMode::Once,
+fn spawn_track(commands: &mut Commands, source: Handle<AudioSource>, is_layer: bool) -> Entity {
+    commands
+        .spawn((
+            AudioBundle {
+                source,
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Despawn,
+                    volume: bevy::audio::Volume::new_relative(0.0),
+                    ..Default::default()
+                },
+            },
+            MusicTrack {
+                is_layer,
                 ..Default::default()
             },
-            ..Default::default()
-        },
-        MusicPlayer::default(),
-    ));
+        ))
+        .id()
 }
 
 fn play_music(
     mut commands: Commands,
-    mut query: Query<(
-        Entity,
-        &mut Handle<AudioSource>,
-        &mut MusicPlayer,
-        Option<&AudioSink>,
-    )>,
+    mut query: Query<&mut MusicTrack>,
+    mut player: ResMut<MusicPlayer>,
     music_assets: Res<MusicAssets>,
+    durations: Res<TrackDurations>,
+    context: Res<MusicContext>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time<Real>>,
 ) {
-    if music_assets.tracks.is_empty() {
-        return;
+    if player.context != Some(*context) {
+        // Crossfade into the music for the new context
+        player.fade_out_current(&mut query);
+        player.context = Some(*context);
+        player.position = 0;
+    }
+
+    if let Some(current) = player.current {
+        // Start the next track while this one fades out, so it's done fading as it ends. Tracks
+        // that haven't been measured yet just play until they stop.
+        let ending = durations
+            .known
+            .get(&current.source)
+            .is_some_and(|duration| {
+                time.elapsed_seconds() - current.started >= duration - CROSSFADE_TIME
+            });
+        if query.contains(current.track) && !ending {
+            return;
+        }
+        player.fade_out_current(&mut query);
+        player.position += 1;
     }
 
-    for (e, mut audio, mut player, sink) in query.iter_mut() {
-        if let Some(sink) = sink {
-            if !sink.empty() {
+    let tracks = music_assets.tracks(*context);
+    for offset in 0..tracks.len() {
+        let index = (player.position + offset) % tracks.len();
+        let track = &tracks[index];
+        // Anything that's missing or still loading is skipped for now
+        if asset_server.get_load_state(&track.track) != Some(LoadState::Loaded) {
+            continue;
+        }
+
+        let layer = track
+            .layer
+            .as_ref()
+            .filter(|layer| asset_server.get_load_state(*layer) == Some(LoadState::Loaded))
+            .map(|layer| spawn_track(&mut commands, layer.clone(), true));
+
+        player.position = index;
+        player.current = Some(PlayingTrack {
+            track: spawn_track(&mut commands, track.track.clone(), false),
+            layer,
+            source: track.track.id(),
+            started: time.elapsed_seconds(),
+        });
+        break;
+    }
+}
+
+fn fade_music(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut MusicTrack, Option<&AudioSink>)>,
+    volume_settings: Res<VolumeSettings>,
+    intensity: Res<MusicIntensity>,
+    time: Res<Time<Real>>,
+) {
+    // Real time, so music keeps fading while the game is paused
+    let step = time.delta_seconds() / CROSSFADE_TIME;
+    for (e, mut track, sink) in query.iter_mut() {
+        if track.fading_out {
+            track.gain -= step;
+            if track.gain <= 0.0 {
+                commands.entity(e).despawn_recursive();
                 continue;
             }
+        } else {
+            track.gain = (track.gain + step).min(1.0);
         }
-        // Either there's nothing playing or it was never started in the first place
 
-        let track_no = if let Some(track) = player.track {
-            (track + 1) % music_assets.tracks.len()
-        } else {
-            0
+        let Some(sink) = sink else {
+            continue;
         };
+        let layer_volume = if track.is_layer { intensity.0 } else { 1.0 };
+        sink.set_volume(volume_settings.volume(AudioChannel::Music) * track.gain * layer_volume);
+    }
+}
 
-        player.track = Some(track_no);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioChannel {
+    Music,
+    /// Sound effects from things happening in the game, see `sfx.rs`
+    Sfx,
+    /// Interface cues, like levelling up
+    Ui,
+}
 
-        *audio = music_assets.tracks[track_no].clone();
-        commands.entity(e).remove::<AudioSink>();
-    }
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelVolume {
+    pub volume: f32,
+    pub muted: bool,
 }
 
-fn handle_volume_change(query: Query<&AudioSink>, volume_settings: Res<VolumeSettings>) {
-    if volume_settings.is_changed() {
-        for sink in query.iter() {
-            sink.set_volume(volume_settings.volume);
+impl Default for ChannelVolume {
+    fn default() -> Self {
+        ChannelVolume {
+            volume: 1.0,
+            muted: false,
         }
     }
 }
 
-fn handle_new_sinks(
-    query: Query<&AudioSink, Added<AudioSink>>,
-    volume_settings: Res<VolumeSettings>,
-) {
-    for sink in query.iter() {
-        sink.set_volume(volume_settings.volume);
+impl ChannelVolume {
+    fn new(volume: f32) -> Self {
+        ChannelVolume {
+            volume,
+            muted: false,
+        }
+    }
+
+    fn effective(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
     }
 }
 
-#[derive(Debug, Resource)]
+/// The mixer. Every channel is also scaled by `master`.
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VolumeSettings {
-    pub volume: f32,
+    pub master: ChannelVolume,
+    pub music: ChannelVolume,
+    pub sfx: ChannelVolume,
+    pub ui: ChannelVolume,
 }
 
 impl Default for VolumeSettings {
     fn default() -> Self {
-        VolumeSettings { volume: 0.5 }
+        VolumeSettings {
+            master: ChannelVolume::new(1.0),
+            music: ChannelVolume::new(0.5),
+            sfx: ChannelVolume::new(0.5),
+            ui: ChannelVolume::new(0.5),
+        }
+    }
+}
+
+impl VolumeSettings {
+    /// Returns the final volume of everything playing on `channel`
+    pub fn volume(&self, channel: AudioChannel) -> f32 {
+        let channel = match channel {
+            AudioChannel::Music => &self.music,
+            AudioChannel::Sfx => &self.sfx,
+            AudioChannel::Ui => &self.ui,
+        };
+        self.master.effective() * channel.effective()
     }
 }
 
@@ -116,8 +478,24 @@ pub struct AudioPlugin;
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<VolumeSettings>()
-            .add_systems(Startup, load_music)
-            .add_systems(OnExit(AppState::Loading), setup_music)
-            .add_systems(Update, (handle_volume_change, handle_new_sinks, play_music));
+            .init_resource::<MusicContext>()
+            .init_resource::<MusicIntensity>()
+            .init_resource::<MusicPlayer>()
+            .init_resource::<TrackDurations>()
+            .init_asset::<Playlist>()
+            .init_asset_loader::<PlaylistLoader>()
+            .add_systems(Startup, load_playlist)
+            .add_systems(OnExit(AppState::Loading), load_tracks)
+            .add_systems(
+                Update,
+                (
+                    update_music_context,
+                    update_music_intensity,
+                    measure_tracks,
+                    play_music,
+                    fade_music,
+                )
+                    .chain(),
+            );
     }
 }
@@ -0,0 +1,184 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    debug::DebugOverlay, enemy::Enemy, health::Health, localization::Localization, physics,
+    player::Player, states::AppState,
+};
+
+/// Enemies further away than this are never targeted
+const MAX_RANGE: f32 = 12.0;
+/// Size of the grid cells enemies are grouped into for `AutoAimMode::Densest`
+const CLUSTER_CELL_SIZE: f32 = 1.5;
+/// How often a new target is picked, in seconds. In between, the current one is followed.
+const RETARGET_INTERVAL: f32 = 0.15;
+/// Most candidates checked for walls in the way each time a target is picked
+const MAX_RAYCASTS: usize = 8;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutoAimMode {
+    #[default]
+    Off,
+    /// Aim at the closest enemy that isn't behind a wall
+    Nearest,
+    /// Aim at whichever visible enemy has the most other enemies around it
+    Densest,
+}
+
+impl AutoAimMode {
+    pub const ALL: [AutoAimMode; 3] =
+        [AutoAimMode::Off, AutoAimMode::Nearest, AutoAimMode::Densest];
+
+    pub fn name<'a>(&self, localization: &'a Localization) -> &'a str {
+        localization.get(match self {
+            AutoAimMode::Off => "auto_aim.off",
+            AutoAimMode::Nearest => "auto_aim.nearest",
+            AutoAimMode::Densest => "auto_aim.densest",
+        })
+    }
+}
+
+/// Lets the game do the aiming and shooting, for players who can't or would rather not.
+/// Also handy for leaving the game running to test balance.
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoAimSettings {
+    pub mode: AutoAimMode,
+    /// Keep firing without holding the fire button
+    pub auto_fire: bool,
+}
+
+/// The enemy position auto aim picked this frame, if any
+#[derive(Debug, Default, Resource)]
+pub struct AutoAimTarget {
+    pub target: Option<Vec2>,
+    /// The enemy being aimed at, followed between retargets
+    entity: Option<Entity>,
+    until_retarget: f32,
+}
+
+impl AutoAimTarget {
+    /// Direction from `from` towards the target, or `None` to fall back to the player's own aim
+    pub fn direction(&self, from: Vec2) -> Option<Vec2> {
+        self.target
+            .map(|target| (target - from).normalize_or_zero())
+    }
+}
+
+fn find_target(
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<(Entity, &Transform, &Health), With<Enemy>>,
+    rapier_context: Res<RapierContext>,
+    settings: Res<AutoAimSettings>,
+    mut target: ResMut<AutoAimTarget>,
+    debug_overlay: Res<DebugOverlay>,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+) {
+    target.target = None;
+    if settings.mode == AutoAimMode::Off {
+        target.entity = None;
+        return;
+    }
+    let Ok(player_transform) = player_query.get_single() else {
+        target.entity = None;
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+
+    // Same filter enemies use to see the player in `move_enemies`
+    let query_filter = QueryFilter::new().groups(CollisionGroups::new(
+        physics::WALL_GROUP,
+        physics::WALL_GROUP,
+    ));
+    let visible = |enemy_pos: Vec2| {
+        rapier_context
+            .cast_ray(player_pos, enemy_pos - player_pos, 1.0, true, query_filter)
+            .is_none()
+    };
+
+    // Keep following the current target until it's time to look again, as long as it can still
+    // be shot at
+    target.until_retarget -= time.delta_seconds();
+    let current = target
+        .entity
+        .and_then(|entity| enemy_query.get(entity).ok())
+        .filter(|(_, _, health)| !health.dead)
+        .map(|(_, transform, _)| transform.translation.truncate())
+        .filter(|enemy_pos| enemy_pos.distance(player_pos) <= MAX_RANGE && visible(*enemy_pos));
+    if current.is_some() && target.until_retarget > 0.0 {
+        target.target = current;
+    } else {
+        target.until_retarget = RETARGET_INTERVAL;
+
+        let mut candidates: Vec<(Entity, Vec2, f32)> = enemy_query
+            .iter()
+            .filter(|(_, _, health)| !health.dead)
+            .map(|(entity, transform, _)| {
+                let enemy_pos = transform.translation.truncate();
+                (entity, enemy_pos, enemy_pos.distance(player_pos))
+            })
+            .filter(|(_, _, distance)| *distance <= MAX_RANGE)
+            .collect();
+
+        if settings.mode == AutoAimMode::Densest {
+            candidates = densest_first(&candidates);
+        } else {
+            candidates.sort_by(|a, b| a.2.total_cmp(&b.2));
+        }
+
+        let picked = candidates
+            .into_iter()
+            .take(MAX_RAYCASTS)
+            .find(|(_, enemy_pos, _)| visible(*enemy_pos));
+        target.entity = picked.map(|(entity, _, _)| entity);
+        target.target = picked.map(|(_, enemy_pos, _)| enemy_pos);
+    }
+
+    if debug_overlay.enabled {
+        if let Some(target) = target.target {
+            gizmos.circle_2d(target, 0.5, Color::RED);
+        }
+    }
+}
+
+/// The nearest enemy of each group, starting with the biggest groups
+///
+/// Enemies are bucketed into a grid, and a group is everything in a cell and the cells around it,
+/// which keeps this fast with hundreds of enemies around.
+fn densest_first(candidates: &[(Entity, Vec2, f32)]) -> Vec<(Entity, Vec2, f32)> {
+    let mut cells: HashMap<IVec2, Vec<(Entity, Vec2, f32)>> = HashMap::default();
+    for candidate in candidates {
+        cells
+            .entry((candidate.1 / CLUSTER_CELL_SIZE).floor().as_ivec2())
+            .or_default()
+            .push(*candidate);
+    }
+
+    let mut groups: Vec<(usize, (Entity, Vec2, f32))> = cells
+        .iter()
+        .filter_map(|(cell, enemies)| {
+            let size = (-1..=1)
+                .flat_map(|x| (-1..=1).map(move |y| IVec2::new(x, y)))
+                .filter_map(|offset| cells.get(&(*cell + offset)))
+                .map(Vec::len)
+                .sum();
+            let nearest = enemies.iter().min_by(|a, b| a.2.total_cmp(&b.2))?;
+            Some((size, *nearest))
+        })
+        .collect();
+    // Break ties in favour of closer groups
+    groups.sort_by(|a, b| b.0.cmp(&a.0).then(a.1 .2.total_cmp(&b.1 .2)));
+    groups.into_iter().map(|(_, nearest)| nearest).collect()
+}
+
+pub struct AutoAimPlugin;
+
+impl Plugin for AutoAimPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutoAimSettings>()
+            .init_resource::<AutoAimTarget>()
+            .add_systems(Update, find_target.run_if(in_state(AppState::InGame)));
+    }
+}
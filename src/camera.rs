@@ -1,18 +1,41 @@
-use bevy::{math::vec2, prelude::*, render::camera::ScalingMode};
+use bevy::{audio::SpatialListener, prelude::*, render::camera::ScalingMode};
 
-use crate::{map, physics, player::Player, states::AppState};
+use crate::{
+    accessibility::AccessibilitySettings,
+    enemy::{BigGhost, Enemy},
+    health::{DamageTaken, DeathEvent, Health},
+    map, physics,
+    player::Player,
+    sfx,
+    states::AppState,
+};
+
+/// The projection's near plane is at the camera, so anything drawn has to stay below this
+pub const CAMERA_Z: f32 = 5.0;
 
 #[derive(Component, Debug, Default)]
 pub struct MainCamera {
     pub bounds: Rect,
 }
 
+#[derive(Debug, Resource)]
+pub struct CameraSettings {
+    /// Multiplies the strength of screen shake, 0 turns it off
+    pub screen_shake: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings { screen_shake: 1.0 }
+    }
+}
+
 fn spawn_camera(mut commands: Commands) {
     commands.spawn((
         Camera2dBundle {
-            transform: Transform::from_xyz(0.0, 0.0, 5.0).looking_to(Vec3::NEG_Z, Vec3::Y),
+            transform: Transform::from_xyz(0.0, 0.0, CAMERA_Z).looking_to(Vec3::NEG_Z, Vec3::Y),
             projection: OrthographicProjection {
-                scaling_mode: ScalingMode::FixedVertical(8.0),
+                scaling_mode: ScalingMode::FixedVertical(BASE_VIEW_HEIGHT),
                 ..Default::default()
             },
             ..Default::default()
@@ -24,6 +47,9 @@ fn spawn_camera(mut commands: Commands) {
             ),
             ..Default::default()
         },
+        CameraMotion::default(),
+        // Positional sound effects are heard from the camera
+        SpatialListener::new(sfx::EAR_GAP),
     ));
 }
 
@@ -33,33 +59,194 @@ fn cleanup_camera(mut commands: Commands, query: Query<Entity, With<MainCamera>>
     }
 }
 
+/// Height of the view in world units with no enemies around
+const BASE_VIEW_HEIGHT: f32 = 8.0;
+/// Height of the view in world units once `CROWD_SIZE` enemies are nearby
+const CROWDED_VIEW_HEIGHT: f32 = 10.0;
+const CROWD_SIZE: f32 = 60.0;
+/// Enemies further than this from the player don't count towards zooming out
+const CROWD_RANGE: f32 = 10.0;
+/// How quickly the zoom catches up, per second
+const ZOOM_RATE: f32 = 1.5;
+/// How far ahead of the player the camera looks, towards where they're aiming
+const LOOK_AHEAD: f32 = 1.0;
+/// Spring constants for following the player. Critically damped, so it settles without
+/// overshooting.
+const FOLLOW_STIFFNESS: f32 = 60.0;
+const FOLLOW_DAMPING: f32 = 15.5;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+/// Furthest the camera is moved and turned at full trauma
+const MAX_SHAKE_OFFSET: f32 = 0.3;
+const MAX_SHAKE_ANGLE: f32 = 0.05;
+/// Trauma added when a big ghost dies
+const BIG_GHOST_DEATH_TRAUMA: f32 = 0.5;
+/// Trauma added when the player loses all of their health at once, less for smaller hits
+const PLAYER_DAMAGE_TRAUMA: f32 = 2.0;
+
+/// Where the camera is heading and how much it's shaking
+#[derive(Component, Debug, Default)]
+struct CameraMotion {
+    position: Vec2,
+    velocity: Vec2,
+    view_height: f32,
+    /// Goes from 0 to 1, shake grows with its square so small hits barely register
+    trauma: f32,
+}
+
+/// Keeps a view of `size` centered as close to `position` as it can while staying inside
+/// `bounds`. Views bigger than the bounds are centered on them instead.
+fn clamp_to_bounds(position: Vec2, size: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + size / 2.0;
+    let max = bounds.max - size / 2.0;
+    let center = bounds.center();
+    Vec2::new(
+        if min.x <= max.x {
+            position.x.clamp(min.x, max.x)
+        } else {
+            center.x
+        },
+        if min.y <= max.y {
+            position.y.clamp(min.y, max.y)
+        } else {
+            center.y
+        },
+    )
+}
+
+fn add_trauma(
+    mut camera_query: Query<&mut CameraMotion>,
+    player_query: Query<&Health, With<Player>>,
+    big_ghost_query: Query<(), With<BigGhost>>,
+    mut damage_reader: EventReader<DamageTaken>,
+    mut death_reader: EventReader<DeathEvent>,
+) {
+    let Ok(mut motion) = camera_query.get_single_mut() else {
+        return;
+    };
+    for ev in damage_reader.read() {
+        if let Ok(health) = player_query.get(ev.entity) {
+            motion.trauma += PLAYER_DAMAGE_TRAUMA * ev.amount / health.maximum;
+        }
+    }
+    for ev in death_reader.read() {
+        if big_ghost_query.contains(ev.entity) {
+            motion.trauma += BIG_GHOST_DEATH_TRAUMA;
+        }
+    }
+    motion.trauma = motion.trauma.min(1.0);
+}
+
 fn camera_follow(
-    player_query: Query<&Transform, (Without<MainCamera>, With<Player>)>,
-    mut camera_query: Query<(&mut Transform, &MainCamera, &OrthographicProjection)>,
+    player_query: Query<(&Transform, &Player), Without<MainCamera>>,
+    enemy_query: Query<&Transform, (With<Enemy>, Without<MainCamera>)>,
+    mut camera_query: Query<(
+        &mut Transform,
+        &mut OrthographicProjection,
+        &mut CameraMotion,
+        &MainCamera,
+    )>,
+    camera_settings: Res<CameraSettings>,
+    accessibility: Res<AccessibilitySettings>,
+    time: Res<Time>,
 ) {
-    let Ok((mut camera_transform, main_camera, projection)) = camera_query.get_single_mut() else {
+    let Ok((mut camera_transform, mut projection, mut motion, main_camera)) =
+        camera_query.get_single_mut()
+    else {
         error!("More than one main camera!");
         return;
     };
 
-    let Ok(player_transform) = player_query.get_single() else {
+    let Ok((player_transform, player)) = player_query.get_single() else {
         return;
     };
+    let dt = time.delta_seconds();
+    let player_position = player_transform.translation.truncate();
+
+    // Zoom out as the crowd grows, so there's room to see what's coming
+    let target_height = if accessibility.reduced_motion {
+        BASE_VIEW_HEIGHT
+    } else {
+        let nearby = enemy_query
+            .iter()
+            .filter(|t| t.translation.truncate().distance(player_position) <= CROWD_RANGE)
+            .count();
+        BASE_VIEW_HEIGHT
+            + (CROWDED_VIEW_HEIGHT - BASE_VIEW_HEIGHT) * (nearby as f32 / CROWD_SIZE).min(1.0)
+    };
+    // Start out on the player rather than flying in from the middle of the map
+    let just_spawned = motion.view_height == 0.0;
+    if just_spawned {
+        motion.view_height = target_height;
+    }
+    motion.view_height += (target_height - motion.view_height) * (ZOOM_RATE * dt).min(1.0);
+    // Only touch the projection when the zoom actually changes, it's recalculated on change
+    let zoom_changed = match projection.scaling_mode {
+        ScalingMode::FixedVertical(height) => height != motion.view_height,
+        _ => true,
+    };
+    if zoom_changed {
+        projection.scaling_mode = ScalingMode::FixedVertical(motion.view_height);
+    }
+    // `area` isn't updated for the new scaling mode until later this frame, and is empty until the
+    // camera has been drawn once
+    let aspect_ratio = if projection.area.height() > 0.0 {
+        projection.area.width() / projection.area.height()
+    } else {
+        1.0
+    };
+    let view_size = Vec2::new(motion.view_height * aspect_ratio, motion.view_height);
+
+    let look_ahead = if accessibility.reduced_motion {
+        Vec2::ZERO
+    } else {
+        player.facing * LOOK_AHEAD
+    };
+    let target = clamp_to_bounds(player_position + look_ahead, view_size, main_camera.bounds);
+
+    if just_spawned {
+        motion.position = target;
+    }
+    let acceleration =
+        FOLLOW_STIFFNESS * (target - motion.position) - FOLLOW_DAMPING * motion.velocity;
+    motion.velocity += acceleration * dt;
+    let velocity = motion.velocity;
+    motion.position += velocity * dt;
+
+    let shake_strength = if accessibility.reduced_motion {
+        0.0
+    } else {
+        camera_settings.screen_shake * motion.trauma * motion.trauma
+    };
+    motion.trauma = (motion.trauma - TRAUMA_DECAY * dt).max(0.0);
 
-    let desired_position = player_transform.translation.truncate().clamp(
-        main_camera.bounds.min + vec2(projection.area.width(), projection.area.height()) / 2.0,
-        main_camera.bounds.max + vec2(-projection.area.width(), -projection.area.height()) / 2.0,
-    );
+    // Overlapping waves at unrelated frequencies make for cheap, smooth noise
+    let t = time.elapsed_seconds();
+    let noise = |seed: f32| ((t * 37.0 + seed).sin() + (t * 23.0 + seed * 1.7).sin()) / 2.0;
+    let shake_offset = Vec2::new(noise(0.0), noise(10.0)) * MAX_SHAKE_OFFSET * shake_strength;
+    let shake_angle = noise(20.0) * MAX_SHAKE_ANGLE * shake_strength;
 
-    camera_transform.translation = desired_position.extend(5.0);
+    camera_transform.translation = clamp_to_bounds(
+        motion.position + shake_offset,
+        view_size,
+        main_camera.bounds,
+    )
+    .extend(CAMERA_Z);
+    camera_transform.rotation = Quat::from_rotation_z(shake_angle);
 }
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(AppState::InGame), spawn_camera)
+        app.init_resource::<CameraSettings>()
+            .add_systems(OnEnter(AppState::InGame), spawn_camera)
             .add_systems(OnExit(AppState::InGame), cleanup_camera)
-            .add_systems(Update, camera_follow.run_if(in_state(AppState::InGame)));
+            .add_systems(
+                Update,
+                (add_trauma, camera_follow)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
     }
 }
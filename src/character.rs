@@ -11,20 +11,34 @@ use crate::{
 
 use rand::Rng;
 
-#[derive(Debug, Default, Component)]
+#[derive(Debug, Component)]
 pub struct Character {
     pub max_speed: f32,
     pub acceleration: f32,
+    /// Scales `max_speed`, used by status effects like slow and freeze
+    pub speed_multiplier: f32,
 
     pub desired_direction: Vec2,
 }
 
-fn move_character(mut query: Query<(&Character, &mut Velocity)>, time: Res<Time>) {
+impl Default for Character {
+    fn default() -> Self {
+        Character {
+            max_speed: 0.0,
+            acceleration: 0.0,
+            speed_multiplier: 1.0,
+            desired_direction: Vec2::ZERO,
+        }
+    }
+}
+
+pub fn move_character(mut query: Query<(&Character, &mut Velocity)>, time: Res<Time>) {
     let dt = time.delta_seconds();
     for (character, mut velocity) in query.iter_mut() {
         let vel = velocity.linvel;
 
-        let desired_velocity = character.desired_direction * character.max_speed;
+        let desired_velocity =
+            character.desired_direction * character.max_speed * character.speed_multiplier;
         let diff = desired_velocity - vel;
 
         velocity.linvel += diff * character.acceleration * dt;
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    character::{self, Character},
+    devices::Upgradeable,
+    health::{Health, Invulnerability},
+    healthbar::{BarKind, HealthbarMaterial},
+    input::{Action, ActionState},
+    player::Player,
+    states::AppState,
+};
+
+/// How long a single dash lasts, in seconds
+const DASH_DURATION: f32 = 0.15;
+/// How long the player is invulnerable after starting a dash, in seconds
+const DASH_INVULNERABILITY: f32 = 0.25;
+
+#[derive(Debug, Component, Clone)]
+pub struct Dash {
+    pub speed: Upgradeable,
+    pub cooldown: Upgradeable,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        Dash {
+            speed: Upgradeable::new(12.0),
+            cooldown: Upgradeable::new(2.0),
+        }
+    }
+}
+
+#[derive(Debug, Component, Default)]
+pub struct DashState {
+    pub cooldown_remaining: f32,
+    pub dash_remaining: f32,
+    pub direction: Vec2,
+}
+
+impl DashState {
+    pub fn is_dashing(&self) -> bool {
+        self.dash_remaining > 0.0
+    }
+}
+
+#[derive(Component, Debug, Default)]
+struct DashCooldownBar;
+
+#[derive(Component, Debug, Default)]
+struct HasDashCooldownBar {
+    material: Handle<HealthbarMaterial>,
+}
+
+fn setup_dash(
+    mut commands: Commands,
+    query: Query<Entity, Added<Dash>>,
+    mut materials: ResMut<Assets<HealthbarMaterial>>,
+    accessibility: Res<AccessibilitySettings>,
+) {
+    for e in query.iter() {
+        let material = materials.add(HealthbarMaterial::new(BarKind::Dash, accessibility.palette));
+
+        commands.entity(e).insert((
+            DashState::default(),
+            HasDashCooldownBar {
+                material: material.clone(),
+            },
+        ));
+
+        commands.spawn((
+            MaterialNodeBundle::<HealthbarMaterial> {
+                style: Style {
+                    left: Val::Percent(45.0),
+                    right: Val::Percent(45.0),
+                    height: Val::Px(8.0),
+                    top: Val::Px(60.0),
+                    position_type: PositionType::Absolute,
+                    ..Default::default()
+                },
+                material,
+                ..Default::default()
+            },
+            DashCooldownBar,
+        ));
+    }
+}
+
+fn start_dash(
+    mut query: Query<(
+        &Player,
+        &Dash,
+        &mut DashState,
+        &Character,
+        &Health,
+        Option<&mut Invulnerability>,
+    )>,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (player, dash, mut state, character, health, invulnerability) in query.iter_mut() {
+        if state.cooldown_remaining > 0.0 {
+            state.cooldown_remaining -= dt;
+        }
+        if state.is_dashing() {
+            state.dash_remaining -= dt;
+        }
+
+        if !actions.just_pressed(Action::Dash)
+            || health.dead
+            || state.cooldown_remaining > 0.0
+            || character.speed_multiplier <= 0.0
+        {
+            continue;
+        }
+
+        // Dash the way the player is moving, or the way they're facing if they're standing still
+        let direction = if character.desired_direction != Vec2::ZERO {
+            character.desired_direction.normalize_or_zero()
+        } else {
+            player.facing
+        };
+
+        state.direction = direction;
+        state.dash_remaining = DASH_DURATION;
+        state.cooldown_remaining = dash.cooldown.value();
+
+        if let Some(mut invulnerability) = invulnerability {
+            invulnerability.grant(DASH_INVULNERABILITY);
+        }
+    }
+}
+
+fn apply_dash(mut query: Query<(&Dash, &DashState, &mut Velocity)>) {
+    for (dash, state, mut velocity) in query.iter_mut() {
+        if state.is_dashing() {
+            // This goes through the physics engine like any other movement, so walls still stop the dash
+            velocity.linvel = state.direction * dash.speed.value();
+        }
+    }
+}
+
+fn update_dash_cooldown_bar(
+    query: Query<(&Dash, &DashState, &HasDashCooldownBar)>,
+    mut materials: ResMut<Assets<HealthbarMaterial>>,
+) {
+    for (dash, state, bar) in query.iter() {
+        let Some(mat) = materials.get_mut(&bar.material) else {
+            continue;
+        };
+
+        mat.fraction = 1.0 - (state.cooldown_remaining / dash.cooldown.value()).clamp(0.0, 1.0);
+    }
+}
+
+fn cleanup_dash(mut commands: Commands, query: Query<Entity, With<DashCooldownBar>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct DashPlugin;
+
+impl Plugin for DashPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                setup_dash,
+                start_dash,
+                apply_dash.after(character::move_character),
+                update_dash_cooldown_bar,
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), cleanup_dash);
+    }
+}
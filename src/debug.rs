@@ -1,14 +1,17 @@
 use bevy::prelude::*;
 
-use crate::states::AppState;
+use crate::{
+    input::{Action, ActionState},
+    states::AppState,
+};
 
 #[derive(Debug, Default, Resource)]
 pub struct DebugOverlay {
     pub enabled: bool,
 }
 
-fn toggle_debug(mut overlay: ResMut<DebugOverlay>, input: Res<Input<KeyCode>>) {
-    if input.just_pressed(KeyCode::Backslash) {
+fn toggle_debug(mut overlay: ResMut<DebugOverlay>, actions: Res<ActionState>) {
+    if actions.just_pressed(Action::ToggleDebug) {
         overlay.enabled = !overlay.enabled
     }
 }
@@ -1,14 +1,19 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 
 use crate::{
+    auto_aim::{AutoAimSettings, AutoAimTarget},
     enemy::Enemy,
-    health::{DamageEvent, Health},
+    health::{DamageEvent, DamageType, Health},
+    input::{Action, ActionState},
     loading::LoadingAssets,
     map::{EnemySpawner, Wall},
+    particles::{ParticleBurst, ParticleEffect, ParticleEmitter},
     physics,
+    sfx::{PlaySfx, Sfx},
     states::AppState,
+    status::{ApplyStatusEffect, StatusEffect, StatusEffects},
 };
 
 use super::Upgradeable;
@@ -55,6 +60,18 @@ fn setup_fireball_launcher(mut commands: Commands, query: Query<Entity, Added<Fi
     }
 }
 
+/// Chance for a fireball to deal `CRIT_MULTIPLIER` times its damage
+const CRIT_CHANCE: f64 = 0.05;
+const CRIT_MULTIPLIER: f32 = 2.0;
+
+/// Burn applied to every enemy a fireball hits
+const BURN_DAMAGE_PER_SECOND: f32 = 0.2;
+const BURN_DURATION: f32 = 1.5;
+/// Drawn above characters and the lighting overlay, so fireballs keep glowing in the dark
+const FIREBALL_Z: f32 = 1.3;
+/// How long a critical hit stuns an enemy for
+const CRIT_STUN_DURATION: f32 = 0.4;
+
 #[derive(Debug, Default, Component)]
 pub struct Fireball {
     pub damage: f32,
@@ -83,19 +100,56 @@ struct FireballBundle {
 
     pub sprite: Sprite,
     pub texture: Handle<Image>,
+
+    pub trail: ParticleEmitter,
+}
+
+/// Embers left behind a fireball as it flies
+fn trail_effect(texture: Handle<Image>) -> ParticleEffect {
+    ParticleEffect {
+        lifetime: 0.3,
+        min_speed: 0.0,
+        max_speed: 0.3,
+        start_color: Color::rgba(1.0, 0.8, 0.4, 0.8),
+        end_color: Color::rgba(1.0, 0.2, 0.0, 0.0),
+        start_size: 0.12,
+        end_size: 0.04,
+        texture: Some(texture),
+        ..Default::default()
+    }
+}
+
+/// Sparks thrown out when a fireball hits something
+fn impact_effect() -> ParticleEffect {
+    ParticleEffect {
+        lifetime: 0.25,
+        min_speed: 1.0,
+        max_speed: 3.0,
+        drag: 0.05,
+        start_color: Color::rgb(1.0, 0.9, 0.5),
+        end_color: Color::rgba(1.0, 0.3, 0.0, 0.0),
+        start_size: 0.06,
+        end_size: 0.02,
+        ..Default::default()
+    }
 }
 
 fn handle_fireball_collisions(
     mut commands: Commands,
-    mut fireball_query: Query<(Entity, &mut Fireball), Without<crate::enemy::Enemy>>,
+    mut fireball_query: Query<(Entity, &mut Fireball, &Transform), Without<crate::enemy::Enemy>>,
     other_query: Query<(Entity, Option<&Enemy>, Option<&Wall>, Option<&EnemySpawner>)>,
     mut collision_events: EventReader<CollisionEvent>,
     mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut particle_bursts: EventWriter<ParticleBurst>,
 ) {
     for ev in collision_events.read() {
         match ev {
             CollisionEvent::Started(e1, e2, _) => {
-                let ((fireball_entity, mut fireball), (other_entity, enemy, wall, spawner)) = {
+                let (
+                    (fireball_entity, mut fireball, fireball_transform),
+                    (other_entity, enemy, wall, spawner),
+                ) = {
                     if let (Ok(fireball), Ok(other)) =
                         (fireball_query.get_mut(*e1), other_query.get(*e2))
                     {
@@ -109,15 +163,46 @@ fn handle_fireball_collisions(
                     }
                 };
 
+                let mut impact = |count| {
+                    particle_bursts.send(ParticleBurst {
+                        effect: impact_effect(),
+                        position: fireball_transform.translation,
+                        count,
+                    });
+                };
+
                 if let Some(_enemy) = enemy {
                     // If the thing it hit is an enemy:
                     if fireball.punch_through >= 1.0 {
                         fireball.punch_through -= 1.0;
 
+                        let crit = rand::thread_rng().gen_bool(CRIT_CHANCE);
                         damage_events.send(DamageEvent {
                             entity: other_entity,
-                            amount: fireball.damage,
+                            amount: if crit {
+                                fireball.damage * CRIT_MULTIPLIER
+                            } else {
+                                fireball.damage
+                            },
+                            damage_type: DamageType::Fire,
+                            crit,
+                            over_time: false,
                         });
+                        status_events.send(ApplyStatusEffect {
+                            entity: other_entity,
+                            effect: StatusEffect::Burn {
+                                damage_per_second: BURN_DAMAGE_PER_SECOND,
+                            },
+                            duration: BURN_DURATION,
+                        });
+                        if crit {
+                            status_events.send(ApplyStatusEffect {
+                                entity: other_entity,
+                                effect: StatusEffect::Stun,
+                                duration: CRIT_STUN_DURATION,
+                            });
+                        }
+                        impact(if crit { 16 } else { 6 });
                     }
 
                     if fireball.punch_through < 1.0 {
@@ -129,10 +214,12 @@ fn handle_fireball_collisions(
                 if let Some(_wall) = wall {
                     // If the thing it hit is a wall:
                     commands.entity(fireball_entity).despawn_recursive();
+                    impact(6);
                 }
                 if let Some(_spawner) = spawner {
                     // ... Or a spawner
                     commands.entity(fireball_entity).despawn_recursive();
+                    impact(6);
                 }
             }
             _ => {
@@ -144,31 +231,19 @@ fn handle_fireball_collisions(
 
 fn aim_fireball_launcher(
     mut query: Query<(&mut FireballLauncherState, &Transform)>,
-    main_window_query: Query<&Window, With<PrimaryWindow>>,
-    main_camera_query: Query<(&Camera, &GlobalTransform)>,
+    actions: Res<ActionState>,
+    auto_aim: Res<AutoAimTarget>,
 ) {
-    let Ok(main_window) = main_window_query.get_single() else {
-        return;
-    };
-
-    let Ok((main_camera, camera_transform)) = main_camera_query.get_single() else {
-        error!("Didn't find exactly one camera! make a marker component or something");
-        return;
-    };
-
     for (mut launcher_state, transform) in query.iter_mut() {
         let launcher_pos = transform.translation.truncate();
 
-        let Some(cursor_pos) = main_window.cursor_position() else {
-            continue;
-        };
-        let Some(cursor_pos) = main_camera.viewport_to_world_2d(camera_transform, cursor_pos)
+        let Some(dir) = auto_aim
+            .direction(launcher_pos)
+            .or_else(|| actions.aim_direction(launcher_pos))
         else {
             continue;
         };
 
-        let dir = (cursor_pos - launcher_pos).normalize_or_zero();
-
         launcher_state.direction = dir;
     }
 }
@@ -180,15 +255,18 @@ fn fireball_launcher(
         &FireballLauncher,
         &mut FireballLauncherState,
         &Health,
+        Option<&StatusEffects>,
     )>,
-    input: Res<Input<MouseButton>>,
+    actions: Res<ActionState>,
+    auto_aim_settings: Res<AutoAimSettings>,
     fireball_assets: Res<FireballAssets>,
+    mut sfx_writer: EventWriter<PlaySfx>,
     time: Res<Time>,
 ) {
     const LAUNCH_DISTANCE: f32 = 0.2;
-    let pressed = input.pressed(MouseButton::Left);
+    let pressed = actions.pressed(Action::Fire) || auto_aim_settings.auto_fire;
 
-    for (transform, launcher, mut state, health) in query.iter_mut() {
+    for (transform, launcher, mut state, health, status_effects) in query.iter_mut() {
         if health.dead {
             continue;
         }
@@ -199,7 +277,9 @@ fn fireball_launcher(
         let n_shots =
             ((state.time_since_last_shot / launcher.fire_delay.value()).floor() as u32).min(3);
 
-        if pressed && n_shots > 0 {
+        let stunned = status_effects.is_some_and(|s| s.is_stunned());
+
+        if pressed && !stunned && n_shots > 0 {
             //multishot
             state.multishot_acc += launcher.multishot.value();
             let multishots = state.multishot_acc.floor() as u32;
@@ -220,12 +300,17 @@ fn fireball_launcher(
                         ..Default::default()
                     },
                     transform: Transform::from_translation(
-                        transform.translation + state.direction.extend(1.3) * LAUNCH_DISTANCE,
+                        (transform.translation.truncate() + state.direction * LAUNCH_DISTANCE)
+                            .extend(FIREBALL_Z),
                     )
                     .with_scale(Vec3::splat(physics::PHYSICS_SCALE) * 0.5)
                     .with_rotation(Quat::from_rotation_z(Vec2::X.angle_between(velocity))),
                     velocity: Velocity::linear(velocity),
                     texture: fireball_assets.texture.clone(),
+                    trail: ParticleEmitter::new(
+                        trail_effect(fireball_assets.texture.clone()),
+                        30.0,
+                    ),
                     collider: Collider::ball(0.2 / physics::PHYSICS_SCALE),
                     collision_groups: CollisionGroups::new(
                         physics::PROJECTILE_GROUP,
@@ -237,6 +322,12 @@ fn fireball_launcher(
                 });
             }
 
+            if multishots > 0 {
+                sfx_writer.send(PlaySfx::at(
+                    Sfx::FireballLaunch,
+                    transform.translation.truncate(),
+                ));
+            }
             state.time_since_last_shot = 0.0;
         }
     }
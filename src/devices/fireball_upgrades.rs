@@ -4,7 +4,10 @@ use bevy_egui::{
     *,
 };
 
-use crate::{experience::ExperienceCounter, states::GameState, ui::square_button};
+use crate::{
+    dash::Dash, experience::ExperienceCounter, localization::Localization, states::GameState,
+    ui::square_button,
+};
 
 use super::fireball::FireballLauncher;
 
@@ -44,19 +47,39 @@ fn format_multishot(buf: &mut String, value: f32) {
     write!(buf, "+{:.0}%", (value - 1.0) * 100.0).unwrap();
 }
 
+// dash speed: Upgradeable,
+fn dash_speed_formula(level: u32) -> f32 {
+    level as f32 * 0.1 + 1.0
+}
+fn format_dash_speed(buf: &mut String, value: f32) {
+    use std::fmt::Write;
+    write!(buf, "{:.01}m/s", value).unwrap();
+}
+// dash cooldown: Upgradeable,
+fn dash_cooldown_formula(level: u32) -> f32 {
+    1.0 / (level as f32 * 0.15 + 1.0)
+}
+fn format_dash_cooldown(buf: &mut String, value: f32) {
+    use std::fmt::Write;
+    write!(buf, "{:.02}s", value).unwrap();
+}
+
 fn fireball_launcher_upgrade_menu(
     mut contexts: EguiContexts,
-    mut query: Query<(&mut FireballLauncher, &mut ExperienceCounter)>,
+    mut query: Query<(&mut FireballLauncher, &mut Dash, &mut ExperienceCounter)>,
     mut writer: EventWriter<FinishedUpgrading>,
     mut next_state: ResMut<NextState<GameState>>,
     mut modifiable_launcher: Local<Option<FireballLauncher>>,
     mut initial_state: Local<Option<FireballLauncher>>,
-    mut reserved_strings: Local<[String; 5]>,
+    mut modifiable_dash: Local<Option<Dash>>,
+    mut initial_dash: Local<Option<Dash>>,
+    mut reserved_strings: Local<[String; 7]>,
     mut free_points_local: Local<Option<u32>>,
+    localization: Res<Localization>,
 ) {
     let ctx = contexts.ctx_mut();
 
-    let Ok((mut launcher, mut experience_counter)) = query.get_single_mut() else {
+    let Ok((mut launcher, mut dash, mut experience_counter)) = query.get_single_mut() else {
         // TODO: if I ever add other devices, this function should only run if fireballs are equipped
         warn!("No fireball launcher found");
         return;
@@ -72,27 +95,27 @@ fn fireball_launcher_upgrade_menu(
         *initial_state = Some(launcher.clone());
     }
 
+    if modifiable_dash.is_none() {
+        *modifiable_dash = Some(dash.clone());
+        *initial_dash = Some(dash.clone());
+    }
+
     let local_launcher = modifiable_launcher.as_mut().expect("Set above");
+    let local_dash = modifiable_dash.as_mut().expect("Set above");
     for s in reserved_strings.iter_mut() {
         s.clear();
     }
 
-    let confirm_response = egui::Window::new("Upgrades")
+    let confirm_response = egui::Window::new(localization.get("upgrades.title"))
+        .id(egui::Id::new("upgrades"))
         .default_width(600.0)
         .resizable(false)
         .movable(false)
         .anchor(Align2::CENTER_CENTER, egui::Vec2::ZERO)
         .collapsible(false)
         .show(&ctx, |ui| {
-            {
-                use std::fmt::Write;
-                write!(
-                    &mut reserved_strings[4],
-                    "Remaining points: {}",
-                    free_points
-                )
-                .unwrap();
-            }
+            reserved_strings[4]
+                .push_str(&localization.format("upgrades.remaining", &[("points", &*free_points)]));
             ui.label(&reserved_strings[4]);
             ui.separator();
 
@@ -100,8 +123,11 @@ fn fireball_launcher_upgrade_menu(
                 &mut reserved_strings[0],
                 local_launcher.launch_speed.value(),
             );
-            let (minus_response, plus_response) =
-                adjuster(ui, "Launch Speed", &reserved_strings[0]);
+            let (minus_response, plus_response) = adjuster(
+                ui,
+                localization.get("upgrades.launch_speed"),
+                &reserved_strings[0],
+            );
             if minus_response.clicked() {
                 let cur_level = local_launcher.launch_speed.points_spent;
                 if cur_level > initial_state.as_ref().unwrap().launch_speed.points_spent {
@@ -122,7 +148,11 @@ fn fireball_launcher_upgrade_menu(
             }
 
             format_fire_delay(&mut reserved_strings[1], local_launcher.fire_delay.value());
-            let (minus_response, plus_response) = adjuster(ui, "Fire Rate", &reserved_strings[1]);
+            let (minus_response, plus_response) = adjuster(
+                ui,
+                localization.get("upgrades.fire_rate"),
+                &reserved_strings[1],
+            );
             if minus_response.clicked() {
                 let cur_level = local_launcher.fire_delay.points_spent;
                 if cur_level > initial_state.as_ref().unwrap().fire_delay.points_spent {
@@ -146,8 +176,11 @@ fn fireball_launcher_upgrade_menu(
                 &mut reserved_strings[2],
                 local_launcher.punch_through.value(),
             );
-            let (minus_response, plus_response) =
-                adjuster(ui, "Punchthrough", &reserved_strings[2]);
+            let (minus_response, plus_response) = adjuster(
+                ui,
+                localization.get("upgrades.punchthrough"),
+                &reserved_strings[2],
+            );
             if minus_response.clicked() {
                 let cur_level = local_launcher.punch_through.points_spent;
                 if cur_level > initial_state.as_ref().unwrap().punch_through.points_spent {
@@ -168,7 +201,11 @@ fn fireball_launcher_upgrade_menu(
             }
 
             format_multishot(&mut reserved_strings[3], local_launcher.multishot.value());
-            let (minus_response, plus_response) = adjuster(ui, "Multishot", &reserved_strings[3]);
+            let (minus_response, plus_response) = adjuster(
+                ui,
+                localization.get("upgrades.multishot"),
+                &reserved_strings[3],
+            );
             if minus_response.clicked() {
                 let cur_level = local_launcher.multishot.points_spent;
                 if cur_level > initial_state.as_ref().unwrap().multishot.points_spent {
@@ -188,16 +225,67 @@ fn fireball_launcher_upgrade_menu(
                 }
             }
 
+            format_dash_speed(&mut reserved_strings[5], local_dash.speed.value());
+            let (minus_response, plus_response) = adjuster(
+                ui,
+                localization.get("upgrades.dash_speed"),
+                &reserved_strings[5],
+            );
+            if minus_response.clicked() {
+                let cur_level = local_dash.speed.points_spent;
+                if cur_level > initial_dash.as_ref().unwrap().speed.points_spent {
+                    local_dash
+                        .speed
+                        .from_formula(cur_level.saturating_sub(1), dash_speed_formula);
+                    *free_points += 1;
+                }
+            }
+            if plus_response.clicked() {
+                let cur_level = local_dash.speed.points_spent;
+                if *free_points > 0 {
+                    local_dash
+                        .speed
+                        .from_formula(cur_level.saturating_add(1), dash_speed_formula);
+                    *free_points -= 1;
+                }
+            }
+
+            format_dash_cooldown(&mut reserved_strings[6], local_dash.cooldown.value());
+            let (minus_response, plus_response) = adjuster(
+                ui,
+                localization.get("upgrades.dash_cooldown"),
+                &reserved_strings[6],
+            );
+            if minus_response.clicked() {
+                let cur_level = local_dash.cooldown.points_spent;
+                if cur_level > initial_dash.as_ref().unwrap().cooldown.points_spent {
+                    local_dash
+                        .cooldown
+                        .from_formula(cur_level.saturating_sub(1), dash_cooldown_formula);
+                    *free_points += 1;
+                }
+            }
+            if plus_response.clicked() {
+                let cur_level = local_dash.cooldown.points_spent;
+                if *free_points > 0 {
+                    local_dash
+                        .cooldown
+                        .from_formula(cur_level.saturating_add(1), dash_cooldown_formula);
+                    *free_points -= 1;
+                }
+            }
+
             return ui
                 .with_layout(
                     Layout::default()
                         .with_cross_align(egui::Align::RIGHT)
                         .with_cross_justify(false),
-                    |ui| ui.add(square_button("Confirm")),
+                    |ui| ui.add(square_button(localization.get("upgrades.confirm"))),
                 )
                 .inner;
         });
 
+    // Spending points takes the Confirm button, so a stray tap can't skip the choice
     match confirm_response {
         None => {
             // Window is not open, probably shouldn't happen
@@ -211,6 +299,7 @@ fn fireball_launcher_upgrade_menu(
         }) => {
             if confirm.clicked() {
                 *launcher = modifiable_launcher.take().unwrap();
+                *dash = modifiable_dash.take().unwrap();
                 next_state.set(GameState::Playing);
                 let spent = experience_counter.upgrade_points() - *free_points;
                 experience_counter.spend_points(spent);
@@ -221,6 +310,8 @@ fn fireball_launcher_upgrade_menu(
                 // Reset local state so nothing leaks between uses
                 *modifiable_launcher = None;
                 *initial_state = None;
+                *modifiable_dash = None;
+                *initial_dash = None;
             }
         }
     }
@@ -3,6 +3,7 @@ use bevy::prelude::*;
 use crate::{
     devices::fireball_upgrades::FinishedUpgrading,
     loading::GlobalFont,
+    localization::Localization,
     states::{AppState, GameState},
 };
 
@@ -87,7 +88,11 @@ fn setup_difficulty(mut commands: Commands) {
 #[derive(Component, Debug, Default)]
 struct SplashMarker;
 
-fn setup_splash(mut commands: Commands, global_font: Res<GlobalFont>) {
+fn setup_splash(
+    mut commands: Commands,
+    global_font: Res<GlobalFont>,
+    localization: Res<Localization>,
+) {
     commands
         .spawn((
             NodeBundle {
@@ -106,7 +111,7 @@ fn setup_splash(mut commands: Commands, global_font: Res<GlobalFont>) {
             parent.spawn((
                 TextBundle {
                     text: Text::from_section(
-                        "Night 1",
+                        localization.format("night", &[("night", &1)]),
                         TextStyle {
                             font: global_font.0.clone(),
                             font_size: 216.0,
@@ -133,6 +138,7 @@ fn next_night_delay(
     time: Res<Time>,
     difficulty: Res<Difficulty>,
     global_font: Res<GlobalFont>,
+    localization: Res<Localization>,
     mut elapsed: Local<f32>,
     mut finished: Local<bool>,
 ) {
@@ -142,7 +148,7 @@ fn next_night_delay(
         let night = difficulty.night;
         for (mut text, mut visibility) in splash_query.iter_mut() {
             *text = Text::from_section(
-                format!("Night {}", night + 1),
+                localization.format("night", &[("night", &(night + 1))]),
                 TextStyle {
                     font: global_font.0.clone(),
                     font_size: 216.0,
@@ -2,7 +2,9 @@ use bevy::prelude::*;
 
 use crate::{
     difficulty::Difficulty,
+    input::{Action, ActionState, InputBindings},
     loading::{GlobalFont, LoadingAssets},
+    localization::Localization,
     states::AppState,
 };
 
@@ -30,7 +32,22 @@ fn setup_end(
     end_assets: Res<EndAssets>,
     global_font: Res<GlobalFont>,
     difficulty: Res<Difficulty>,
+    localization: Res<Localization>,
+    bindings: Res<InputBindings>,
 ) {
+    let text = [
+        localization.plural("end.nights", difficulty.night, &[]),
+        localization.format(
+            "end.restart",
+            &[("key", &bindings.prompt(Action::Confirm, &localization))],
+        ),
+        localization.format(
+            "end.exit",
+            &[("key", &bindings.prompt(Action::Back, &localization))],
+        ),
+    ]
+    .join("\n");
+
     commands.spawn((
         Camera2dBundle {
             camera_2d: Camera2d {
@@ -73,11 +90,7 @@ fn setup_end(
                     ..Default::default()
                 },
                 text: Text::from_section(
-                    if difficulty.night == 1 {
-                        format!("You worked the Night Shift for {} night\nPress SPACE to restart\nPress ESCAPE to return to Main Menu", difficulty.night)
-                    } else {
-                        format!("You worked the Night Shift for {} nights\nPress SPACE to restart\nPress ESCAPE to return to Main Menu", difficulty.night)
-                    },
+                    text,
                     TextStyle {
                         font: global_font.0.clone(),
                         font_size: 36.0,
@@ -95,11 +108,11 @@ fn cleanup_end(mut commands: Commands, query: Query<Entity, With<EndMarker>>) {
     }
 }
 
-fn handle_start(mut next_state: ResMut<NextState<AppState>>, input: Res<Input<KeyCode>>) {
-    if input.just_released(KeyCode::Space) {
+fn handle_start(mut next_state: ResMut<NextState<AppState>>, actions: Res<ActionState>) {
+    if actions.just_released(Action::Confirm) {
         next_state.set(AppState::Restart);
     }
-    if input.just_released(KeyCode::Escape) {
+    if actions.just_released(Action::Back) {
         next_state.set(AppState::MainMenu);
     }
 }
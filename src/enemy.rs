@@ -1,43 +1,57 @@
 use std::f32::consts::PI;
 
-use bevy::{math::vec3, prelude::*};
+use bevy::{math::vec3, prelude::*, utils::HashMap};
 use bevy_rapier2d::prelude::*;
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 
 use crate::{
+    animation::{build_atlas, frame_index, AnimationClip, AnimationSet, Animator, Clip, Facing},
     character,
     debug::DebugOverlay,
     difficulty::{Difficulty, NightFinished},
     experience::SpawnExperience,
-    health::{DeathEvent, Health},
+    health::{DamageType, DeathEvent, Health, Resistances},
+    health_orb::{SpawnHealthOrb, HEALTH_ORB_AMOUNT},
     loading::LoadingAssets,
     map::EnemySpawner,
+    particles::{ParticleBurst, ParticleEffect},
     pathfinding::Pathfinder,
     physics,
+    sfx::{PlaySfx, Sfx},
     states::AppState,
+    status::{StatusEffect, StatusEffects},
 };
 
 #[derive(Component, Debug, Default)]
 pub struct Enemy {
     pub experience_dropped: f32,
+    /// Chance between 0 and 1 to drop a health orb on death
+    pub health_orb_chance: f32,
     pub healthbar_offset: f32,
     pub healthbar_width: f32,
     pub target: Option<Vec2>,
     pub facing: Vec2,
     pub knockback: f32,
     pub damage: f32,
+    /// Minimum time between two hits on the player while touching them
+    pub contact_cooldown: f32,
+    pub contact_cooldown_remaining: f32,
+    pub damage_type: DamageType,
+    /// Applied to the player along with the damage of every hit, each for its number of seconds
+    pub contact_effects: Vec<(StatusEffect, f32)>,
 }
 
 #[derive(Component, Debug, Default)]
 struct Ghost;
 #[derive(Component, Debug, Default)]
-struct BigGhost;
+pub struct BigGhost;
 
 #[derive(Bundle, Default)]
 pub struct EnemyBundle {
     pub enemy: Enemy,
     pub character: character::Character,
     pub health: Health,
+    pub status_effects: StatusEffects,
 
     pub rigid_body: RigidBody,
     pub velocity: Velocity,
@@ -53,8 +67,9 @@ pub struct EnemyBundle {
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 
-    pub sprite: Sprite,
-    pub texture: Handle<Image>,
+    pub sprite: TextureAtlasSprite,
+    pub texture_atlas: Handle<TextureAtlas>,
+    pub animator: Animator,
 }
 
 fn move_enemies(
@@ -126,86 +141,221 @@ fn move_enemies(
     }
 }
 
-fn face_enemies(
-    mut query: Query<(&Enemy, &mut Handle<Image>, AnyOf<(&Ghost, &BigGhost)>)>,
-    enemy_assets: Res<EnemyAssets>,
-) {
-    for (enemy, mut texture, (ghost, big_ghost)) in query.iter_mut() {
-        if enemy.facing.x <= 0.0 {
-            // facing left
-            if ghost.is_some() {
-                *texture = enemy_assets.ghost_left_texture.clone();
-            } else if big_ghost.is_some() {
-                *texture = enemy_assets.big_ghost_left_texture.clone();
-            }
-        } else {
-            // facing right
-            if ghost.is_some() {
-                *texture = enemy_assets.ghost_right_texture.clone();
-            } else if big_ghost.is_some() {
-                *texture = enemy_assets.big_ghost_right_texture.clone();
-            }
-        }
+fn face_enemies(mut query: Query<(&Enemy, &mut Animator)>) {
+    for (enemy, mut animator) in query.iter_mut() {
+        // Ghosts only have left and right facing art
+        animator.facing = Facing::from_direction_horizontal(enemy.facing);
     }
 }
 
+/// How long the death animation lasts before the enemy is despawned, in seconds
+const DEATH_DURATION: f32 = 0.5;
+
+/// Marks an enemy that has been killed and is playing its death animation
+#[derive(Component, Debug, Default)]
+pub struct Dying {
+    remaining: f32,
+    initial_scale: Vec3,
+}
+
 fn handle_enemy_death(
     mut commands: Commands,
-    query: Query<(&Transform, &Enemy)>,
+    query: Query<(&Transform, &Enemy, Option<&BigGhost>)>,
     mut death_events: EventReader<DeathEvent>,
     mut spawn_experience: EventWriter<SpawnExperience>,
+    mut spawn_health_orb: EventWriter<SpawnHealthOrb>,
+    mut particle_bursts: EventWriter<ParticleBurst>,
+    mut sfx_writer: EventWriter<PlaySfx>,
 ) {
-    // TODO: do something fancier, like an animation, play a sound, etc.
     for ev in death_events.read() {
-        if let Ok((death_pos, enemy)) = query.get(ev.entity) {
-            commands.entity(ev.entity).despawn_recursive();
+        if let Ok((death_pos, enemy, big_ghost)) = query.get(ev.entity) {
             spawn_experience.send(SpawnExperience {
                 amount: enemy.experience_dropped,
                 position: death_pos.translation.truncate(),
             });
+            if rand::thread_rng().gen_bool(enemy.health_orb_chance.clamp(0.0, 1.0) as f64) {
+                spawn_health_orb.send(SpawnHealthOrb {
+                    amount: HEALTH_ORB_AMOUNT,
+                    position: death_pos.translation.truncate(),
+                });
+            }
+
+            // Stop it from moving, colliding or counting as an enemy while it fades out
+            commands
+                .entity(ev.entity)
+                .remove::<(Enemy, character::Character)>()
+                .insert((
+                    Dying {
+                        remaining: DEATH_DURATION,
+                        initial_scale: death_pos.scale,
+                    },
+                    ColliderDisabled,
+                    Velocity::zero(),
+                ))
+                // Get rid of the healthbar
+                .despawn_descendants();
+
+            particle_bursts.send(ParticleBurst {
+                effect: ParticleEffect {
+                    lifetime: DEATH_DURATION,
+                    min_speed: 0.5,
+                    max_speed: 1.5,
+                    drag: 0.2,
+                    start_color: Color::rgba(0.7, 0.4, 1.0, 0.8),
+                    end_color: Color::rgba(0.7, 0.4, 1.0, 0.0),
+                    start_size: 0.08,
+                    end_size: 0.08,
+                    ..Default::default()
+                },
+                position: death_pos.translation.truncate().extend(1.4),
+                count: if big_ghost.is_some() { 24 } else { 8 },
+            });
+
+            sfx_writer.send(PlaySfx {
+                speed: if big_ghost.is_some() { 0.66 } else { 1.0 },
+                ..PlaySfx::at(Sfx::EnemyDeath, death_pos.translation.truncate())
+            });
         }
     }
 }
 
-#[derive(Debug, Resource)]
+fn animate_dying_enemies(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Dying, &mut Transform, &mut TextureAtlasSprite)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut dying, mut transform, mut sprite) in query.iter_mut() {
+        dying.remaining -= dt;
+        if dying.remaining <= 0.0 {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+
+        // Fade out while drifting upwards and stretching, like the ghost is dissipating
+        let t = 1.0 - dying.remaining / DEATH_DURATION;
+        sprite.color.set_a(1.0 - t);
+        transform.scale = dying.initial_scale * Vec3::new(1.0 - 0.5 * t, 1.0 + 0.5 * t, 1.0);
+        transform.translation.y += 0.5 * dt;
+    }
+}
+
+#[derive(Debug, Default, Resource)]
 struct EnemyAssets {
-    ghost_left_texture: Handle<Image>,
-    ghost_right_texture: Handle<Image>,
-    big_ghost_left_texture: Handle<Image>,
-    big_ghost_right_texture: Handle<Image>,
+    /// Individual frames, packed into a single atlas by `build_enemy_animations` once they've loaded
+    frames: Vec<Handle<Image>>,
+    atlas: Handle<TextureAtlas>,
+    ghost_animations: Handle<AnimationSet>,
+    big_ghost_animations: Handle<AnimationSet>,
 }
 
+// Indices into `EnemyAssets::frames`
+const FRAME_GHOST_LEFT: usize = 0;
+const FRAME_GHOST_RIGHT: usize = 1;
+const FRAME_BIG_GHOST_LEFT: usize = 2;
+const FRAME_BIG_GHOST_RIGHT: usize = 3;
+
 fn load_enemy_assets(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut loading_assets: ResMut<LoadingAssets>,
 ) {
-    let ghost_left_texture = asset_server.load::<Image>("textures/ghost left.png");
-    let ghost_right_texture = asset_server.load::<Image>("textures/ghost right.png");
-    let big_ghost_left_texture = asset_server.load::<Image>("textures/big ghost left.png");
-    let big_ghost_right_texture = asset_server.load::<Image>("textures/big ghost right.png");
-
-    loading_assets.add(ghost_left_texture.clone());
-    loading_assets.add(ghost_right_texture.clone());
-    loading_assets.add(big_ghost_left_texture.clone());
-    loading_assets.add(big_ghost_right_texture.clone());
+    let frames: Vec<Handle<Image>> = [
+        "textures/ghost left.png",
+        "textures/ghost right.png",
+        "textures/big ghost left.png",
+        "textures/big ghost right.png",
+    ]
+    .into_iter()
+    .map(|path| asset_server.load(path))
+    .collect();
+
+    for frame in frames.iter() {
+        loading_assets.add(frame.clone());
+    }
 
     commands.insert_resource(EnemyAssets {
-        ghost_left_texture,
-        ghost_right_texture,
-        big_ghost_left_texture,
-        big_ghost_right_texture,
+        frames,
+        ..Default::default()
     });
 }
 
-#[derive(Debug, Default)]
-struct SpawnEnemiesState {
+fn build_enemy_animations(
+    mut enemy_assets: ResMut<EnemyAssets>,
+    mut textures: ResMut<Assets<Image>>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    mut animation_sets: ResMut<Assets<AnimationSet>>,
+) {
+    let Some(atlas) = build_atlas(&enemy_assets.frames, &mut textures) else {
+        return;
+    };
+    let frame = |i: usize| frame_index(&atlas, &enemy_assets.frames[i]);
+
+    // Walking falls back to idle. There are no hit or death frames, so those shudder between the
+    // two facings instead, and dying ghosts also fade out, see `animate_dying_enemies`.
+    let clips = |left: usize, right: usize| {
+        let (left, right) = (frame(left), frame(right));
+        HashMap::from([
+            (
+                Clip::Idle(Facing::Left),
+                AnimationClip::new(vec![left], 0.5, true),
+            ),
+            (
+                Clip::Idle(Facing::Right),
+                AnimationClip::new(vec![right], 0.5, true),
+            ),
+            (
+                Clip::Hit,
+                AnimationClip::new(vec![left, right, left, right], 0.05, false),
+            ),
+            (
+                Clip::Death,
+                AnimationClip::new(vec![left, right], 0.12, true),
+            ),
+        ])
+    };
+    let ghost_clips = clips(FRAME_GHOST_LEFT, FRAME_GHOST_RIGHT);
+    let big_ghost_clips = clips(FRAME_BIG_GHOST_LEFT, FRAME_BIG_GHOST_RIGHT);
+
+    let atlas = atlases.add(atlas);
+    enemy_assets.ghost_animations = animation_sets.add(AnimationSet {
+        atlas: atlas.clone(),
+        clips: ghost_clips,
+    });
+    enemy_assets.big_ghost_animations = animation_sets.add(AnimationSet {
+        atlas: atlas.clone(),
+        clips: big_ghost_clips,
+    });
+    enemy_assets.atlas = atlas;
+}
+
+/// How far through spawning tonight's wave `spawn_enemies` is
+#[derive(Debug, Default, Resource)]
+pub struct SpawnEnemiesState {
     enemies_remaining: f32,
     big_enemies_remaining: f32,
     time_since_last_spawn: f32,
     to_spawn: f32,
     to_spawn_big: f32,
     night_finished: bool,
+    /// Picked ahead of time so the spawner can be pointed out before anything comes out of it
+    next_spawner: Option<Vec2>,
+}
+
+impl SpawnEnemiesState {
+    /// Enemies in tonight's wave that haven't spawned yet
+    pub fn unspawned(&self) -> u32 {
+        (self.enemies_remaining + self.to_spawn).floor() as u32
+            + (self.big_enemies_remaining + self.to_spawn_big).floor() as u32
+    }
+
+    /// Where the next batch of enemies will come from, if there are any left to come
+    pub fn next_spawner(&self) -> Option<Vec2> {
+        if self.unspawned() == 0 {
+            return None;
+        }
+        self.next_spawner
+    }
 }
 
 fn spawn_enemies(
@@ -224,7 +374,7 @@ fn spawn_enemies(
     enemy_assets: Res<EnemyAssets>,
     difficulty: Res<Difficulty>,
     time: Res<Time>,
-    mut state: Local<SpawnEnemiesState>,
+    mut state: ResMut<SpawnEnemiesState>,
     mut spawn_locations: Local<Vec<Vec2>>,
 ) {
     if difficulty.is_changed() {
@@ -235,11 +385,15 @@ fn spawn_enemies(
         state.to_spawn = 0.0;
         state.to_spawn_big = 0.0;
         state.night_finished = false;
+        state.next_spawner = None;
     }
 
     if spawn_locations.is_empty() {
         spawn_locations.extend(spawner_query.iter().map(|t| t.translation.truncate()));
     }
+    if state.next_spawner.is_none() {
+        state.next_spawner = spawn_locations.choose(&mut rand::thread_rng()).copied();
+    }
 
     if !state.night_finished
         && state.enemies_remaining + state.to_spawn < 1.0
@@ -276,13 +430,16 @@ fn spawn_enemies(
         let to_spawn = state.to_spawn as u32;
         state.to_spawn -= to_spawn as f32;
 
-        let spawner = spawn_locations[rand::thread_rng().gen_range(0..spawn_locations.len())];
+        let Some(spawner) = state.next_spawner.take() else {
+            return;
+        };
 
         for i in 0..to_spawn {
             let t = (i as f32 / to_spawn as f32) * 2.0 * PI;
             commands.spawn((
                 EnemyBundle {
-                    texture: enemy_assets.ghost_left_texture.clone(),
+                    texture_atlas: enemy_assets.atlas.clone(),
+                    animator: Animator::new(enemy_assets.ghost_animations.clone()),
                     transform: Transform::from_translation(
                         spawner.extend(0.0) + vec3(f32::cos(t) * 0.5, f32::sin(t) * 0.5, 0.0),
                     )
@@ -304,10 +461,14 @@ fn spawn_enemies(
                     health: Health::new(2.0 * difficulty.health_multiplier),
                     enemy: Enemy {
                         experience_dropped: 1.0 * difficulty.experience_multiplier,
+                        health_orb_chance: 0.01,
                         healthbar_offset: 0.65,
                         healthbar_width: 1.0,
                         knockback: 8.0,
                         damage: 1.0 * difficulty.damage_multiplier,
+                        contact_cooldown: 1.0,
+                        // Ghosts are cold to the touch
+                        damage_type: DamageType::Frost,
                         ..Default::default()
                     },
                     friction: Friction {
@@ -332,13 +493,21 @@ fn spawn_enemies(
         let to_spawn_big = state.to_spawn_big as u32;
         state.to_spawn_big -= to_spawn_big as f32;
 
-        let spawner = spawn_locations[rand::thread_rng().gen_range(0..spawn_locations.len())];
+        // Big ghosts come out of their own spawner when they arrive alongside a regular batch
+        let Some(spawner) = state
+            .next_spawner
+            .take()
+            .or_else(|| spawn_locations.choose(&mut rand::thread_rng()).copied())
+        else {
+            return;
+        };
 
         for i in 0..to_spawn_big {
             let t = (i as f32 / to_spawn_big as f32) * 2.0 * PI;
             commands.spawn((
                 EnemyBundle {
-                    texture: enemy_assets.big_ghost_left_texture.clone(),
+                    texture_atlas: enemy_assets.atlas.clone(),
+                    animator: Animator::new(enemy_assets.big_ghost_animations.clone()),
                     transform: Transform::from_translation(
                         spawner.extend(0.0) + vec3(f32::cos(t) * 0.5, f32::sin(t) * 0.5, 0.0),
                     )
@@ -360,21 +529,34 @@ fn spawn_enemies(
                     health: Health::new(30.0 * difficulty.health_multiplier),
                     enemy: Enemy {
                         experience_dropped: 10.0 * difficulty.experience_multiplier,
+                        health_orb_chance: 0.25,
                         healthbar_offset: 1.3,
                         healthbar_width: 3.0,
                         knockback: 20.0,
                         damage: 3.0 * difficulty.damage_multiplier,
+                        contact_cooldown: 1.5,
+                        damage_type: DamageType::Frost,
+                        // Big ones freeze the player in place for a moment, then slow them down
+                        // while they thaw
+                        contact_effects: vec![
+                            (StatusEffect::Freeze, 0.3),
+                            (StatusEffect::Slow { factor: 0.6 }, 1.5),
+                        ],
                         ..Default::default()
                     },
                     ..Default::default()
                 },
                 BigGhost,
+                Resistances {
+                    fire: 0.1,
+                    ..Default::default()
+                },
             ));
         }
     }
 }
 
-fn cleanup_enemies(mut commands: Commands, query: Query<Entity, With<Enemy>>) {
+fn cleanup_enemies(mut commands: Commands, query: Query<Entity, Or<(With<Enemy>, With<Dying>)>>) {
     for e in query.iter() {
         commands.entity(e).despawn_recursive();
     }
@@ -384,12 +566,15 @@ pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, load_enemy_assets)
+        app.init_resource::<SpawnEnemiesState>()
+            .add_systems(Startup, load_enemy_assets)
+            .add_systems(OnExit(AppState::Loading), build_enemy_animations)
             .add_systems(
                 Update,
                 (
                     move_enemies,
                     handle_enemy_death,
+                    animate_dying_enemies,
                     face_enemies,
                     spawn_enemies,
                 )
@@ -4,7 +4,13 @@ use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    healthbar::HealthbarMaterial, loading::LoadingAssets, physics, player::Player, states::AppState,
+    accessibility::AccessibilitySettings,
+    healthbar::{BarKind, HealthbarMaterial},
+    loading::LoadingAssets,
+    particles::{ParticleEffect, ParticleEmitter},
+    physics,
+    player::Player,
+    states::AppState,
 };
 
 #[derive(Component, Debug)]
@@ -67,7 +73,6 @@ impl ExperienceCounter {
         self.current / self.to_next_level
     }
 
-    #[allow(unused)]
     /// Returns the current level
     pub fn level(&self) -> u32 {
         self.level
@@ -88,21 +93,6 @@ impl ExperienceCounter {
     }
 }
 
-#[derive(Resource, Debug)]
-struct ExperienceBarAssets {
-    material: HealthbarMaterial,
-}
-
-fn load_experience_bar_assets(mut commands: Commands) {
-    let material = HealthbarMaterial {
-        filled_color: Color::LIME_GREEN,
-        empty_color: Color::GRAY,
-        fraction: 1.0,
-    };
-
-    commands.insert_resource(ExperienceBarAssets { material });
-}
-
 #[derive(Component, Debug, Default)]
 struct HasExperienceBar {
     material: Handle<HealthbarMaterial>,
@@ -128,10 +118,13 @@ fn setup_experience_bar(
     mut commands: Commands,
     query: Query<Entity, Added<ExperienceCounter>>,
     mut materials: ResMut<Assets<HealthbarMaterial>>,
-    assets: Res<ExperienceBarAssets>,
+    accessibility: Res<AccessibilitySettings>,
 ) {
     for player_entity in query.iter() {
-        let material = materials.add(assets.material.clone());
+        let material = materials.add(HealthbarMaterial::new(
+            BarKind::Experience,
+            accessibility.palette,
+        ));
         commands.entity(player_entity).insert(HasExperienceBar {
             material: material.clone(),
         });
@@ -195,6 +188,8 @@ struct ExperienceOrbBundle {
 
     sprite: Sprite,
     texture: Handle<Image>,
+
+    sparkles: ParticleEmitter,
 }
 
 fn tick_experience_orbs(
@@ -212,31 +207,50 @@ fn tick_experience_orbs(
 
     let dt = time.delta_seconds();
     for (orb_entity, orb_transform, mut orb_velocity, mut orb) in orb_query.iter_mut() {
-        // If the orb is close enough to the player...
-        if orb_transform
-            .translation
-            .truncate()
-            .distance_squared(player_transform.translation.truncate())
-            <= orb_velocity.linvel.length_squared() * dt * dt
-        {
+        let orb = &mut *orb;
+        if home_in(
+            orb_transform.translation.truncate(),
+            player_transform.translation.truncate(),
+            &mut orb_velocity.linvel,
+            &mut orb.speed,
+            &mut orb.max_turn_rate,
+            dt,
+        ) {
             // Destroy this orb and collect the experience
             commands.entity(orb_entity).despawn_recursive();
             collect_experience_writer.send(CollectExperience { amount: orb.amount });
-            continue;
         }
+    }
+}
 
-        // Rotate the orb's velocity towards its target
-        let desired_direction =
-            (player_transform.translation - orb_transform.translation).truncate();
-        let desired_rotation = orb_velocity.linvel.angle_between(desired_direction);
-        let rotation = desired_rotation.clamp(-orb.max_turn_rate * dt, orb.max_turn_rate * dt);
-        orb_velocity.linvel = Vec2::from_angle(rotation)
-            .rotate(orb_velocity.linvel.normalize_or_zero() * f32::max(orb.speed, 1.0));
-
-        // Accelerate the orb
-        orb.speed *= 3.0_f32.powf(dt);
-        orb.max_turn_rate *= 4.0_f32.powf(dt);
+/// Steers an orb at `position` towards `target`, speeding it up and letting it turn faster over time.
+///
+/// Returns `true` if the orb is close enough to `target` to be collected this frame
+pub fn home_in(
+    position: Vec2,
+    target: Vec2,
+    velocity: &mut Vec2,
+    speed: &mut f32,
+    max_turn_rate: &mut f32,
+    dt: f32,
+) -> bool {
+    // If the orb is close enough to the target...
+    if position.distance_squared(target) <= velocity.length_squared() * dt * dt {
+        return true;
     }
+
+    // Rotate the orb's velocity towards its target
+    let desired_direction = target - position;
+    let desired_rotation = velocity.angle_between(desired_direction);
+    let rotation = desired_rotation.clamp(-*max_turn_rate * dt, *max_turn_rate * dt);
+    *velocity =
+        Vec2::from_angle(rotation).rotate(velocity.normalize_or_zero() * f32::max(*speed, 1.0));
+
+    // Accelerate the orb
+    *speed *= 3.0_f32.powf(dt);
+    *max_turn_rate *= 4.0_f32.powf(dt);
+
+    false
 }
 
 #[derive(Event, Debug, Default)]
@@ -269,6 +283,19 @@ fn handle_spawn_experience(
                     speed: 0.5,
                     ..Default::default()
                 },
+                sparkles: ParticleEmitter::new(
+                    ParticleEffect {
+                        lifetime: 0.4,
+                        min_speed: 0.1,
+                        max_speed: 0.4,
+                        start_color: Color::rgba(0.6, 1.0, 0.6, 0.9),
+                        end_color: Color::rgba(0.2, 1.0, 0.2, 0.0),
+                        start_size: 0.05,
+                        end_size: 0.0,
+                        ..Default::default()
+                    },
+                    4.0,
+                ),
                 velocity: Velocity::linear(initial_velocity * 15.0),
                 rigid_body: RigidBody::KinematicVelocityBased,
                 transform: Transform::from_translation(position.extend(1.5))
@@ -284,16 +311,20 @@ pub struct CollectExperience {
     pub amount: f32,
 }
 
+#[derive(Event, Debug, Default)]
+pub struct LevelUp;
+
 fn handle_collect_experience(
     mut query: Query<&mut ExperienceCounter>,
     mut reader: EventReader<CollectExperience>,
+    mut level_up_writer: EventWriter<LevelUp>,
 ) {
     for CollectExperience { amount } in reader.read() {
         for mut counter in query.iter_mut() {
-            // TODO: maybe fire off "level up" events
             let levels_gained = counter.add_experience(*amount);
             if levels_gained > 0 {
-                debug!("Gained {} level(s)!", levels_gained)
+                debug!("Gained {} level(s)!", levels_gained);
+                level_up_writer.send(LevelUp);
             }
         }
     }
@@ -321,10 +352,8 @@ impl Plugin for ExperiencePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnExperience>()
             .add_event::<CollectExperience>()
-            .add_systems(
-                Startup,
-                (load_experience_assets, load_experience_bar_assets),
-            )
+            .add_event::<LevelUp>()
+            .add_systems(Startup, load_experience_assets)
             .add_systems(OnExit(AppState::InGame), cleanup_experience)
             .add_systems(
                 Update,
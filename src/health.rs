@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::states::AppState;
+use crate::{accessibility::AccessibilitySettings, states::AppState};
 
 #[derive(Component, Debug, Default)]
 pub struct Health {
@@ -23,10 +23,62 @@ impl Health {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    #[default]
+    Physical,
+    Fire,
+    Frost,
+}
+
+/// Fraction of incoming damage of each type that is ignored.
+///
+/// Values above 1.0 make an entity immune, negative values make it take extra damage.
+#[derive(Component, Debug, Default, Clone)]
+pub struct Resistances {
+    pub physical: f32,
+    pub fire: f32,
+    pub frost: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Physical => self.physical,
+            DamageType::Fire => self.fire,
+            DamageType::Frost => self.frost,
+        }
+    }
+
+    /// Returns how much of `amount` gets through these resistances
+    pub fn mitigate(&self, amount: f32, damage_type: DamageType) -> f32 {
+        amount * f32::max(1.0 - self.get(damage_type), 0.0)
+    }
+}
+
 #[derive(Event, Debug)]
 pub struct DamageEvent {
     pub entity: Entity,
     pub amount: f32,
+    pub damage_type: DamageType,
+    pub crit: bool,
+    /// Ticks of damage over time, like burning, which don't make anything invulnerable
+    pub over_time: bool,
+}
+
+/// Sent for every `DamageEvent` that actually lands, after invulnerability, resistances and armor
+#[derive(Event, Debug)]
+pub struct DamageTaken {
+    pub entity: Entity,
+    pub amount: f32,
+    pub damage_type: DamageType,
+    pub crit: bool,
+}
+
+#[derive(Event, Debug)]
+pub struct HealEvent {
+    pub entity: Entity,
+    pub amount: f32,
 }
 
 #[derive(Event, Debug)]
@@ -34,18 +86,184 @@ pub struct DeathEvent {
     pub entity: Entity,
 }
 
+/// Ignores all damage for a short while after being hit by something other than damage over time
+#[derive(Component, Debug, Default)]
+pub struct Invulnerability {
+    /// How long the entity stays invulnerable after taking damage, in seconds
+    pub duration: f32,
+    pub remaining: f32,
+}
+
+impl Invulnerability {
+    pub fn new(duration: f32) -> Self {
+        Invulnerability {
+            duration,
+            remaining: 0.0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// Makes the entity invulnerable for at least `duration` seconds
+    pub fn grant(&mut self, duration: f32) {
+        self.remaining = f32::max(self.remaining, duration);
+    }
+}
+
+fn tick_invulnerability(mut query: Query<&mut Invulnerability>, time: Res<Time>) {
+    let dt = time.delta_seconds();
+    for mut invulnerability in query.iter_mut() {
+        if invulnerability.is_active() {
+            invulnerability.remaining -= dt;
+        }
+    }
+}
+
+fn flash_invulnerable(
+    mut query: Query<(&Invulnerability, &Health, &mut TextureAtlasSprite)>,
+    accessibility: Res<AccessibilitySettings>,
+) {
+    // Flashes per second
+    const FLASH_RATE: f32 = 8.0;
+
+    for (invulnerability, health, mut sprite) in query.iter_mut() {
+        // With reduced motion, stay see-through the whole time instead of flashing
+        let faded =
+            accessibility.reduced_motion || (invulnerability.remaining * FLASH_RATE).fract() < 0.5;
+        let alpha = if invulnerability.is_active() && !health.dead && faded {
+            0.3
+        } else {
+            1.0
+        };
+
+        if sprite.color.a() != alpha {
+            sprite.color.set_a(alpha);
+        }
+    }
+}
+
+/// Reduces all incoming damage, applied after resistances
+#[derive(Component, Debug, Default, Clone)]
+pub struct Armor {
+    /// Subtracted from every hit
+    pub flat: f32,
+    /// Fraction of the remaining damage that is ignored
+    pub percent: f32,
+}
+
+impl Armor {
+    pub fn mitigate(&self, amount: f32) -> f32 {
+        f32::max(amount - self.flat, 0.0) * f32::clamp(1.0 - self.percent, 0.0, 1.0)
+    }
+}
+
+/// Absorbs damage before it reaches `Health`, recharging after not taking damage for a while
+#[derive(Component, Debug, Default)]
+pub struct Shield {
+    pub current: f32,
+    pub maximum: f32,
+    /// Seconds without taking damage before the shield starts recharging
+    pub recharge_delay: f32,
+    /// Shield restored per second while recharging
+    pub recharge_rate: f32,
+    pub time_since_damage: f32,
+}
+
+impl Shield {
+    pub fn new(maximum: f32, recharge_delay: f32, recharge_rate: f32) -> Self {
+        Shield {
+            current: maximum,
+            maximum,
+            recharge_delay,
+            recharge_rate,
+            time_since_damage: 0.0,
+        }
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.maximum <= 0.0 {
+            return 0.0;
+        }
+        self.current / self.maximum
+    }
+
+    /// Soaks up as much of `amount` as possible, returning whatever is left over
+    pub fn absorb(&mut self, amount: f32) -> f32 {
+        self.time_since_damage = 0.0;
+        let absorbed = f32::min(self.current, amount);
+        self.current -= absorbed;
+        amount - absorbed
+    }
+}
+
+fn recharge_shields(mut query: Query<(&mut Shield, &Health)>, time: Res<Time>) {
+    let dt = time.delta_seconds();
+    for (mut shield, health) in query.iter_mut() {
+        if health.dead {
+            continue;
+        }
+        shield.time_since_damage += dt;
+        if shield.time_since_damage >= shield.recharge_delay && shield.current < shield.maximum {
+            shield.current = f32::min(shield.current + shield.recharge_rate * dt, shield.maximum);
+        }
+    }
+}
+
 fn process_damage_events(
-    mut query: Query<&mut Health>,
+    mut query: Query<(
+        &mut Health,
+        Option<&Resistances>,
+        Option<&Armor>,
+        Option<&mut Shield>,
+        Option<&mut Invulnerability>,
+    )>,
     mut reader: EventReader<DamageEvent>,
     mut writer: EventWriter<DeathEvent>,
+    mut damage_taken: EventWriter<DamageTaken>,
     mut deaths: Local<Vec<DeathEvent>>,
 ) {
     for ev in reader.read() {
-        let Ok(mut health) = query.get_mut(ev.entity) else {
+        let Ok((mut health, resistances, armor, shield, invulnerability)) =
+            query.get_mut(ev.entity)
+        else {
             continue;
         };
 
-        health.current = f32::clamp(health.current - ev.amount, 0.0, health.maximum);
+        if invulnerability.as_ref().is_some_and(|i| i.is_active()) {
+            continue;
+        }
+
+        let mut amount = match resistances {
+            Some(resistances) => resistances.mitigate(ev.amount, ev.damage_type),
+            None => ev.amount,
+        };
+        if let Some(armor) = armor {
+            amount = armor.mitigate(amount);
+        }
+
+        // Only real hits count, otherwise a burn tick or a fully blocked hit would protect from
+        // the next one
+        if let Some(mut invulnerability) = invulnerability {
+            if amount > 0.0 && !ev.over_time {
+                let duration = invulnerability.duration;
+                invulnerability.grant(duration);
+            }
+        }
+        if !health.dead && amount > 0.0 {
+            damage_taken.send(DamageTaken {
+                entity: ev.entity,
+                amount,
+                damage_type: ev.damage_type,
+                crit: ev.crit,
+            });
+        }
+        if let Some(mut shield) = shield {
+            amount = shield.absorb(amount);
+        }
+
+        health.current = f32::clamp(health.current - amount, 0.0, health.maximum);
 
         if !health.dead && health.current <= 0.0 {
             deaths.push(DeathEvent { entity: ev.entity });
@@ -56,15 +274,182 @@ fn process_damage_events(
     writer.send_batch(deaths.drain(..));
 }
 
+fn process_heal_events(mut query: Query<&mut Health>, mut reader: EventReader<HealEvent>) {
+    for ev in reader.read() {
+        let Ok(mut health) = query.get_mut(ev.entity) else {
+            continue;
+        };
+
+        // Healing can't bring anything back from the dead
+        if health.dead {
+            continue;
+        }
+
+        health.current = f32::clamp(health.current + ev.amount, 0.0, health.maximum);
+    }
+}
+
+/// Restores health over time, in units per second
+#[derive(Component, Debug, Default)]
+pub struct Regeneration {
+    pub per_second: f32,
+}
+
+fn regenerate_health(
+    query: Query<(Entity, &Health, &Regeneration)>,
+    mut writer: EventWriter<HealEvent>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, health, regeneration) in query.iter() {
+        if health.dead || health.current >= health.maximum {
+            continue;
+        }
+        writer.send(HealEvent {
+            entity,
+            amount: regeneration.per_second * dt,
+        });
+    }
+}
+
 pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<DamageEvent>()
+            .add_event::<DamageTaken>()
+            .add_event::<HealEvent>()
             .add_event::<DeathEvent>()
             .add_systems(
                 Update,
-                process_damage_events.run_if(in_state(AppState::InGame)),
+                (
+                    (
+                        tick_invulnerability,
+                        recharge_shields,
+                        regenerate_health,
+                        process_heal_events,
+                        process_damage_events,
+                    )
+                        .chain(),
+                    flash_invulnerable,
+                )
+                    .run_if(in_state(AppState::InGame)),
             );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    /// Each update moves time forward by this much
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .add_event::<DamageEvent>()
+            .add_event::<DamageTaken>()
+            .add_event::<DeathEvent>()
+            .add_systems(
+                Update,
+                (tick_invulnerability, process_damage_events).chain(),
+            );
+        app
+    }
+
+    fn spawn_player(app: &mut App) -> Entity {
+        app.world
+            .spawn((Health::new(10.0), Invulnerability::new(1.0)))
+            .id()
+    }
+
+    fn damage(app: &mut App, entity: Entity, damage_type: DamageType, over_time: bool) {
+        app.world.send_event(DamageEvent {
+            entity,
+            amount: 1.0,
+            damage_type,
+            crit: false,
+            over_time,
+        });
+    }
+
+    fn current_health(app: &App, entity: Entity) -> f32 {
+        app.world.get::<Health>(entity).unwrap().current
+    }
+
+    #[test]
+    fn second_hit_during_invulnerability_is_ignored() {
+        let mut app = test_app();
+        let player = spawn_player(&mut app);
+
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 9.0);
+
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 9.0);
+    }
+
+    #[test]
+    fn hits_in_the_same_frame_only_count_once() {
+        let mut app = test_app();
+        let player = spawn_player(&mut app);
+
+        damage(&mut app, player, DamageType::Physical, false);
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 9.0);
+    }
+
+    #[test]
+    fn hits_land_again_once_invulnerability_runs_out() {
+        let mut app = test_app();
+        let player = spawn_player(&mut app);
+
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        for _ in 0..11 {
+            app.update();
+        }
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 8.0);
+    }
+
+    #[test]
+    fn damage_over_time_does_not_grant_invulnerability() {
+        let mut app = test_app();
+        let player = spawn_player(&mut app);
+
+        damage(&mut app, player, DamageType::Fire, true);
+        app.update();
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 8.0);
+    }
+
+    #[test]
+    fn resisted_hits_do_not_grant_invulnerability() {
+        let mut app = test_app();
+        let player = spawn_player(&mut app);
+        app.world.entity_mut(player).insert(Resistances {
+            frost: 1.0,
+            ..Default::default()
+        });
+
+        damage(&mut app, player, DamageType::Frost, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 10.0);
+
+        damage(&mut app, player, DamageType::Physical, false);
+        app.update();
+        assert_eq!(current_health(&app, player), 9.0);
+    }
+}
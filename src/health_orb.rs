@@ -0,0 +1,145 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    experience::home_in, health::HealEvent, loading::LoadingAssets, physics, player::Player,
+    states::AppState,
+};
+
+/// How much health a single orb restores
+pub const HEALTH_ORB_AMOUNT: f32 = 3.0;
+
+#[derive(Debug, Default, Component)]
+pub struct HealthOrb {
+    // These should both increase over this orb's lifetime
+    pub speed: f32,
+    pub max_turn_rate: f32,
+
+    pub amount: f32,
+}
+
+#[derive(Bundle, Default)]
+struct HealthOrbBundle {
+    health_orb: HealthOrb,
+
+    rigid_body: RigidBody,
+    velocity: Velocity,
+    locked_axes: LockedAxes,
+
+    visibility: Visibility,
+    inherited_visibility: InheritedVisibility,
+    view_visibility: ViewVisibility,
+
+    transform: Transform,
+    global_transform: GlobalTransform,
+
+    sprite: Sprite,
+    texture: Handle<Image>,
+}
+
+#[derive(Event, Debug, Default)]
+pub struct SpawnHealthOrb {
+    pub amount: f32,
+    pub position: Vec2,
+}
+
+fn handle_spawn_health_orb(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnHealthOrb>,
+    health_orb_assets: Res<HealthOrbAssets>,
+    mut previous_angle: Local<f32>,
+) {
+    for SpawnHealthOrb { amount, position } in reader.read() {
+        *previous_angle += PI * 0.618;
+        *previous_angle %= PI * 2.0;
+        let initial_velocity = Vec2::from_angle(*previous_angle);
+
+        commands.spawn(HealthOrbBundle {
+            texture: health_orb_assets.texture.clone(),
+            sprite: Sprite {
+                color: Color::RED,
+                ..Default::default()
+            },
+            health_orb: HealthOrb {
+                amount: *amount,
+                max_turn_rate: PI,
+                speed: 0.5,
+            },
+            velocity: Velocity::linear(initial_velocity * 10.0),
+            rigid_body: RigidBody::KinematicVelocityBased,
+            transform: Transform::from_translation(position.extend(1.5))
+                .with_scale(Vec3::splat(0.7 * physics::PHYSICS_SCALE)),
+            ..Default::default()
+        });
+    }
+}
+
+fn tick_health_orbs(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut orb_query: Query<(Entity, &Transform, &mut Velocity, &mut HealthOrb)>,
+    time: Res<Time>,
+    mut heal_writer: EventWriter<HealEvent>,
+) {
+    let Some((player_entity, player_transform)) = player_query.iter().next() else {
+        return;
+    };
+
+    let dt = time.delta_seconds();
+    for (orb_entity, orb_transform, mut orb_velocity, mut orb) in orb_query.iter_mut() {
+        let orb = &mut *orb;
+        if home_in(
+            orb_transform.translation.truncate(),
+            player_transform.translation.truncate(),
+            &mut orb_velocity.linvel,
+            &mut orb.speed,
+            &mut orb.max_turn_rate,
+            dt,
+        ) {
+            commands.entity(orb_entity).despawn_recursive();
+            heal_writer.send(HealEvent {
+                entity: player_entity,
+                amount: orb.amount,
+            });
+        }
+    }
+}
+
+fn cleanup_health_orbs(mut commands: Commands, query: Query<Entity, With<HealthOrb>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct HealthOrbAssets {
+    texture: Handle<Image>,
+}
+
+fn load_health_orb_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+) {
+    // There's no dedicated texture yet, so reuse the experience orb tinted red
+    let texture = asset_server.load("textures/experience orb.png");
+    loading_assets.add(texture.clone());
+
+    commands.insert_resource(HealthOrbAssets { texture });
+}
+
+pub struct HealthOrbPlugin;
+
+impl Plugin for HealthOrbPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnHealthOrb>()
+            .add_systems(Startup, load_health_orb_assets)
+            .add_systems(OnExit(AppState::InGame), cleanup_health_orbs)
+            .add_systems(
+                Update,
+                (handle_spawn_health_orb, tick_health_orbs).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
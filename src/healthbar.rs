@@ -5,7 +5,18 @@ use bevy::{
     sprite::{Material2d, Material2dPlugin, Mesh2dHandle},
 };
 
-use crate::{enemy::Enemy, health::Health, physics, player::Player, states::AppState};
+use crate::{
+    accessibility::{AccessibilitySettings, ColorPalette},
+    enemy::Enemy,
+    health::{Health, Shield},
+    physics,
+    player::Player,
+    states::AppState,
+};
+
+/// How far above its enemy a health bar is drawn, in world units. Enemies stay within 0.1 of zero,
+/// so this keeps the bars over the lighting overlay.
+const HEALTHBAR_Z: f32 = 1.0;
 
 #[derive(Debug, Default, Component)]
 struct Healthbar;
@@ -32,12 +43,13 @@ struct HasHealthbar {
 }
 
 fn update_healthbars(
-    query: Query<(&Health, &HasHealthbar)>,
+    query: Query<(&Health, Option<&Shield>, &HasHealthbar)>,
     mut healthbar_materials: ResMut<Assets<HealthbarMaterial>>,
 ) {
-    for (health, HasHealthbar { healthbar_mat }) in query.iter() {
+    for (health, shield, HasHealthbar { healthbar_mat }) in query.iter() {
         if let Some(mat) = healthbar_materials.get_mut(healthbar_mat) {
             mat.fraction = health.fraction();
+            mat.shield_fraction = shield.map(|s| s.fraction()).unwrap_or(0.0);
         } else {
             warn!("Health bar has no corresponding material!");
         }
@@ -46,18 +58,18 @@ fn update_healthbars(
 
 fn setup_healthbars(
     mut commands: Commands,
-    query: Query<(Entity, Option<&Enemy>, Option<&Player>), Added<Health>>,
+    query: Query<(Entity, &Transform, Option<&Enemy>, Option<&Player>), Added<Health>>,
     healthbar_assets: Res<HealthbarAssets>,
     mut rolling_offset: Local<f32>,
     mut materials: ResMut<Assets<HealthbarMaterial>>,
+    accessibility: Res<AccessibilitySettings>,
 ) {
-    for (e, enemy, player) in query.iter() {
+    for (e, transform, enemy, player) in query.iter() {
         if let Some(_player) = player {
-            let material = materials.add(HealthbarMaterial {
-                filled_color: Color::RED,
-                empty_color: Color::GRAY,
-                fraction: 1.0,
-            });
+            let material = materials.add(HealthbarMaterial::new(
+                BarKind::PlayerHealth,
+                accessibility.palette,
+            ));
             commands.entity(e).insert(HasHealthbar {
                 healthbar_mat: material.clone(),
             });
@@ -81,7 +93,10 @@ fn setup_healthbars(
             continue;
         }
 
-        let mat = materials.add(healthbar_assets.default_mat.clone());
+        let mat = materials.add(HealthbarMaterial::new(
+            BarKind::EnemyHealth,
+            accessibility.palette,
+        ));
         commands
             .spawn(HealthbarBundle {
                 mesh: healthbar_assets.mesh.clone(),
@@ -90,7 +105,8 @@ fn setup_healthbars(
                 transform: Transform::from_xyz(
                     0.0,
                     enemy.map(|e| e.healthbar_offset).unwrap_or(0.6) / physics::PHYSICS_SCALE,
-                    1.0 + *rolling_offset,
+                    // Undo the enemy's scale, so the bar ends up the same height above every enemy
+                    (HEALTHBAR_Z + *rolling_offset) / transform.scale.z,
                 )
                 .with_scale(
                     vec3(enemy.map(|e| e.healthbar_width).unwrap_or(1.0), 1.0, 1.0)
@@ -107,7 +123,7 @@ fn setup_healthbars(
         // This offset prevents different health bars from z-fighting
         *rolling_offset += 0.001;
     }
-    *rolling_offset %= 2.0;
+    *rolling_offset %= 0.2;
 }
 
 fn cleanup_healthbars(mut commands: Commands, query: Query<Entity, With<Healthbar>>) {
@@ -116,15 +132,50 @@ fn cleanup_healthbars(mut commands: Commands, query: Query<Entity, With<Healthba
     }
 }
 
+/// What a bar shows, which decides its colors, see `ColorPalette::bar_colors`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BarKind {
+    #[default]
+    PlayerHealth,
+    EnemyHealth,
+    Experience,
+    Dash,
+}
+
 #[derive(Asset, TypePath, AsBindGroup, Debug, Default, Clone)]
 #[repr(C)]
 pub struct HealthbarMaterial {
+    /// Not sent to the shader, just remembered so the colors can change with the palette
+    pub kind: BarKind,
     #[uniform(0)]
     pub filled_color: Color,
     #[uniform(0)]
     pub empty_color: Color,
     #[uniform(0)]
     pub fraction: f32,
+    /// Color of the shield stripe drawn over the top of the bar
+    #[uniform(0)]
+    pub shield_color: Color,
+    #[uniform(0)]
+    pub shield_fraction: f32,
+}
+
+impl HealthbarMaterial {
+    /// A full bar colored for `kind`
+    pub fn new(kind: BarKind, palette: ColorPalette) -> Self {
+        let mut material = HealthbarMaterial {
+            kind,
+            fraction: 1.0,
+            ..Default::default()
+        };
+        material.recolor(palette);
+        material
+    }
+
+    pub fn recolor(&mut self, palette: ColorPalette) {
+        (self.filled_color, self.empty_color) = palette.bar_colors(self.kind);
+        self.shield_color = palette.shield_color();
+    }
 }
 
 impl Material for HealthbarMaterial {
@@ -148,7 +199,6 @@ impl UiMaterial for HealthbarMaterial {
 #[derive(Resource, Debug, Default)]
 struct HealthbarAssets {
     pub mesh: Mesh2dHandle,
-    pub default_mat: HealthbarMaterial,
 }
 
 fn load_healthbar_assets(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
@@ -162,17 +212,7 @@ fn load_healthbar_assets(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>
         )
         .into();
 
-    let mat = HealthbarMaterial {
-        fraction: 1.0,
-        filled_color: Color::RED,
-        empty_color: Color::DARK_GRAY,
-        ..Default::default()
-    };
-
-    commands.insert_resource(HealthbarAssets {
-        mesh,
-        default_mat: mat,
-    });
+    commands.insert_resource(HealthbarAssets { mesh });
 }
 
 pub struct HealthbarPlugin;
@@ -0,0 +1,272 @@
+use bevy::{prelude::*, text::Text2dBounds};
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    camera,
+    health::{DamageTaken, DamageType},
+    loading::GlobalFont,
+    physics,
+    player::Player,
+    states::AppState,
+    status::{self, StatusEffects},
+};
+
+/// Maximum number of damage numbers on screen at once. Once they're all in use the oldest one gets recycled.
+const DAMAGE_NUMBER_POOL_SIZE: usize = 128;
+/// How long a damage number stays on screen, in seconds
+const DAMAGE_NUMBER_LIFETIME: f32 = 0.8;
+/// Above orbs and fireballs, but under the camera, which doesn't see anything above itself
+const DAMAGE_NUMBER_Z: f32 = 4.0;
+const _: () = assert!(DAMAGE_NUMBER_Z < camera::CAMERA_Z);
+/// How long a sprite flashes after being hit, in seconds
+const HIT_FLASH_DURATION: f32 = 0.1;
+
+#[derive(Debug, Resource)]
+pub struct DamageNumberSettings {
+    pub enabled: bool,
+}
+
+impl Default for DamageNumberSettings {
+    fn default() -> Self {
+        DamageNumberSettings { enabled: true }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+struct DamageNumber {
+    velocity: Vec2,
+    remaining: f32,
+    color: Color,
+}
+
+#[derive(Resource, Debug, Default)]
+struct DamageNumberPool {
+    entities: Vec<Entity>,
+    next: usize,
+}
+
+fn setup_damage_number_pool(mut commands: Commands, global_font: Res<GlobalFont>) {
+    let mut entities = Vec::with_capacity(DAMAGE_NUMBER_POOL_SIZE);
+    for _ in 0..DAMAGE_NUMBER_POOL_SIZE {
+        entities.push(
+            commands
+                .spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            "",
+                            TextStyle {
+                                font: global_font.0.clone(),
+                                font_size: 32.0,
+                                ..Default::default()
+                            },
+                        ),
+                        text_2d_bounds: Text2dBounds::UNBOUNDED,
+                        visibility: Visibility::Hidden,
+                        ..Default::default()
+                    },
+                    DamageNumber::default(),
+                ))
+                .id(),
+        );
+    }
+
+    commands.insert_resource(DamageNumberPool { entities, next: 0 });
+}
+
+fn cleanup_damage_number_pool(mut commands: Commands, query: Query<Entity, With<DamageNumber>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.remove_resource::<DamageNumberPool>();
+}
+
+fn spawn_damage_numbers(
+    target_query: Query<(&GlobalTransform, Option<&Player>)>,
+    mut number_query: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    mut reader: EventReader<DamageTaken>,
+    mut pool: ResMut<DamageNumberPool>,
+    settings: Res<DamageNumberSettings>,
+    mut previous_offset: Local<f32>,
+) {
+    if !settings.enabled {
+        reader.clear();
+        return;
+    }
+
+    for ev in reader.read() {
+        let Ok((target_transform, player)) = target_query.get(ev.entity) else {
+            continue;
+        };
+
+        let number_entity = pool.entities[pool.next];
+        pool.next = (pool.next + 1) % pool.entities.len();
+
+        let Ok((mut number, mut text, mut transform, mut visibility)) =
+            number_query.get_mut(number_entity)
+        else {
+            continue;
+        };
+
+        let mut color = match ev.damage_type {
+            DamageType::Physical => Color::WHITE,
+            DamageType::Fire => Color::ORANGE,
+            DamageType::Frost => Color::rgb(0.6, 0.85, 1.0),
+        };
+        let mut scale = 1.0;
+        if player.is_some() {
+            color = Color::RED;
+            scale *= 1.25;
+        }
+        if ev.crit {
+            color = Color::YELLOW;
+            scale *= 1.5;
+        }
+
+        let section = &mut text.sections[0];
+        section.value.clear();
+        {
+            use std::fmt::Write;
+            if ev.amount >= 1.0 {
+                write!(&mut section.value, "{:.0}", ev.amount).unwrap();
+            } else {
+                write!(&mut section.value, "{:.1}", ev.amount).unwrap();
+            }
+            if ev.crit {
+                section.value.push('!');
+            }
+        }
+        section.style.color = color;
+
+        // Spread numbers out a bit so they don't all stack on top of each other
+        *previous_offset = (*previous_offset + 0.37) % 1.0;
+        let offset = Vec2::new(*previous_offset - 0.5, 0.5) * 0.5;
+
+        *transform = Transform::from_translation(
+            (target_transform.translation().truncate() + offset).extend(DAMAGE_NUMBER_Z),
+        )
+        .with_scale(Vec3::splat(scale * physics::PHYSICS_SCALE * 0.5));
+        *visibility = Visibility::Visible;
+        *number = DamageNumber {
+            velocity: Vec2::new(offset.x, 1.0),
+            remaining: DAMAGE_NUMBER_LIFETIME,
+            color,
+        };
+    }
+}
+
+fn animate_damage_numbers(
+    mut query: Query<(
+        &mut DamageNumber,
+        &mut Text,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (mut number, mut text, mut transform, mut visibility) in query.iter_mut() {
+        if number.remaining <= 0.0 {
+            continue;
+        }
+
+        number.remaining -= dt;
+        if number.remaining <= 0.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        transform.translation += (number.velocity * dt).extend(0.0);
+        number.velocity *= 0.1_f32.powf(dt);
+
+        let alpha = (number.remaining / DAMAGE_NUMBER_LIFETIME * 2.0).min(1.0);
+        text.sections[0].style.color = number.color.with_a(alpha);
+    }
+}
+
+#[derive(Component, Debug)]
+struct HitFlash {
+    remaining: f32,
+    color: Color,
+}
+
+fn start_hit_flash(
+    mut commands: Commands,
+    query: Query<Option<&Player>, With<TextureAtlasSprite>>,
+    mut reader: EventReader<DamageTaken>,
+    accessibility: Res<AccessibilitySettings>,
+) {
+    if accessibility.reduced_motion {
+        reader.clear();
+        return;
+    }
+
+    for ev in reader.read() {
+        let Ok(player) = query.get(ev.entity) else {
+            continue;
+        };
+
+        // Sprite colors multiply the texture, so going above 1.0 brightens the sprite
+        let color = if player.is_some() {
+            Color::rgb(2.0, 0.5, 0.5)
+        } else {
+            Color::rgb(3.0, 3.0, 3.0)
+        };
+
+        // The entity might have died and been despawned this frame
+        commands.entity(ev.entity).try_insert(HitFlash {
+            remaining: HIT_FLASH_DURATION,
+            color,
+        });
+    }
+}
+
+fn tick_hit_flash(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut HitFlash,
+        &mut TextureAtlasSprite,
+        Option<&StatusEffects>,
+    )>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (e, mut flash, mut sprite, status_effects) in query.iter_mut() {
+        let alpha = sprite.color.a();
+        flash.remaining -= dt;
+
+        if flash.remaining > 0.0 {
+            sprite.color = flash.color.with_a(alpha);
+        } else {
+            // Go back to whatever color the sprite had before being hit
+            let tint = status_effects.map(|s| s.tint()).unwrap_or(Color::WHITE);
+            sprite.color = tint.with_a(alpha);
+            commands.entity(e).remove::<HitFlash>();
+        }
+    }
+}
+
+pub struct HitFeedbackPlugin;
+
+impl Plugin for HitFeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DamageNumberSettings>()
+            .add_systems(OnEnter(AppState::InGame), setup_damage_number_pool)
+            .add_systems(OnExit(AppState::InGame), cleanup_damage_number_pool)
+            .add_systems(
+                Update,
+                (
+                    (spawn_damage_numbers, animate_damage_numbers).chain(),
+                    (start_hit_flash, tick_hit_flash)
+                        .chain()
+                        .after(status::tint_status_effects),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+
+use crate::{
+    dash::DashState,
+    devices::fireball::FireballLauncher,
+    difficulty::{Difficulty, StartNight},
+    enemy::{Dying, Enemy, SpawnEnemiesState},
+    experience::ExperienceCounter,
+    health::Health,
+    loading::GlobalFont,
+    localization::Localization,
+    player::Player,
+    states::AppState,
+};
+
+const FONT_SIZE: f32 = 24.0;
+/// Space between the HUD and the edges of the window
+const MARGIN: f32 = 10.0;
+
+/// Numbers for this run that aren't kept anywhere else
+#[derive(Debug, Default, Resource)]
+pub struct RunStats {
+    pub kills: u32,
+    /// How long the current night has been going, not counting time spent paused
+    pub night_time: f32,
+}
+
+#[derive(Debug, Default, Component)]
+struct HudMarker;
+
+/// What a line of HUD text shows
+#[derive(Debug, Clone, Copy, Component)]
+enum HudText {
+    Night,
+    NightTime,
+    EnemiesLeft,
+    Kills,
+    Health,
+    Level,
+    FireRate,
+    DashCooldown,
+}
+
+fn setup_hud(mut commands: Commands, global_font: Res<GlobalFont>) {
+    commands.insert_resource(RunStats::default());
+
+    let text_style = TextStyle {
+        font: global_font.0.clone(),
+        font_size: FONT_SIZE,
+        ..Default::default()
+    };
+    let mut column = |style: Style, align_items: AlignItems, lines: &[HudText]| {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        flex_direction: FlexDirection::Column,
+                        align_items,
+                        ..style
+                    },
+                    ..Default::default()
+                },
+                HudMarker,
+            ))
+            .with_children(|parent| {
+                for line in lines {
+                    parent.spawn((TextBundle::from_section("", text_style.clone()), *line));
+                }
+            });
+    };
+
+    // How the night is going in the top right, out of the way of the bars along the top
+    column(
+        Style {
+            top: Val::Px(MARGIN),
+            right: Val::Px(MARGIN),
+            ..Default::default()
+        },
+        AlignItems::FlexEnd,
+        &[
+            HudText::Night,
+            HudText::NightTime,
+            HudText::EnemiesLeft,
+            HudText::Kills,
+        ],
+    );
+    // How the player is doing in the bottom left. The top left is where the touch pause button is.
+    column(
+        Style {
+            bottom: Val::Px(MARGIN),
+            left: Val::Px(MARGIN),
+            ..Default::default()
+        },
+        AlignItems::FlexStart,
+        &[
+            HudText::Health,
+            HudText::Level,
+            HudText::FireRate,
+            HudText::DashCooldown,
+        ],
+    );
+}
+
+fn cleanup_hud(mut commands: Commands, query: Query<Entity, With<HudMarker>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn update_run_stats(
+    mut stats: ResMut<RunStats>,
+    dying_query: Query<(), Added<Dying>>,
+    mut start_night: EventReader<StartNight>,
+    time: Res<Time>,
+) {
+    stats.kills += dying_query.iter().count() as u32;
+    if start_night.read().count() > 0 {
+        stats.night_time = 0.0;
+    }
+    // Virtual time stops while paused and upgrading, so this only counts time actually played
+    stats.night_time += time.delta_seconds();
+}
+
+fn update_hud(
+    mut text_query: Query<(&mut Text, &HudText)>,
+    player_query: Query<
+        (
+            &Health,
+            &ExperienceCounter,
+            &FireballLauncher,
+            Option<&DashState>,
+        ),
+        With<Player>,
+    >,
+    enemy_query: Query<(), With<Enemy>>,
+    stats: Res<RunStats>,
+    difficulty: Res<Difficulty>,
+    spawn_state: Res<SpawnEnemiesState>,
+    localization: Res<Localization>,
+) {
+    let player = player_query.get_single().ok();
+
+    for (mut text, hud_text) in text_query.iter_mut() {
+        let value = match hud_text {
+            HudText::Night => localization.format("night", &[("night", &difficulty.night)]),
+            HudText::NightTime => {
+                let seconds = stats.night_time as u32;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            HudText::EnemiesLeft => localization.plural(
+                "hud.enemies_left",
+                spawn_state.unspawned() + enemy_query.iter().count() as u32,
+                &[],
+            ),
+            HudText::Kills => localization.format("hud.kills", &[("kills", &stats.kills)]),
+            HudText::Health => match player {
+                Some((health, ..)) => localization.format(
+                    "hud.health",
+                    &[
+                        ("current", &health.current.max(0.0).ceil()),
+                        ("maximum", &health.maximum.ceil()),
+                    ],
+                ),
+                None => String::new(),
+            },
+            HudText::Level => match player {
+                Some((_, experience, ..)) => {
+                    localization.format("hud.level", &[("level", &experience.level())])
+                }
+                None => String::new(),
+            },
+            HudText::FireRate => match player {
+                Some((_, _, launcher, ..)) => localization.format(
+                    "hud.fire_rate",
+                    &[("rate", &format!("{:.2}", 1.0 / launcher.fire_delay.value()))],
+                ),
+                None => String::new(),
+            },
+            HudText::DashCooldown => match player {
+                Some((_, _, _, Some(dash_state))) if dash_state.cooldown_remaining > 0.0 => {
+                    localization.format(
+                        "hud.dash_cooldown",
+                        &[("seconds", &format!("{:.1}", dash_state.cooldown_remaining))],
+                    )
+                }
+                Some(_) => localization.get("hud.dash_ready").to_owned(),
+                None => String::new(),
+            },
+        };
+
+        // Only touch the text when it changes, so it isn't laid out again every frame
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(OnEnter(AppState::InGame), setup_hud)
+            .add_systems(OnExit(AppState::InGame), cleanup_hud)
+            .add_systems(
+                Update,
+                (update_run_stats, update_hud)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
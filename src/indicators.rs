@@ -0,0 +1,140 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    accessibility::AccessibilitySettings,
+    camera::MainCamera,
+    enemy::{BigGhost, Enemy, SpawnEnemiesState},
+    states::{AppState, GameState},
+};
+
+/// Off-screen enemies are grouped by which of this many slices of the screen they're in
+const SECTORS: usize = 16;
+/// How far in from the edge of the screen arrows are drawn, in egui points
+const EDGE_INSET: f32 = 24.0;
+const ARROW_SIZE: f32 = 10.0;
+/// Arrows stop growing past this many enemies
+const MAX_ARROW_GROUP: f32 = 25.0;
+/// Size of the warning ring drawn on the next spawner, in world units
+const SPAWNER_WARNING_RADIUS: f32 = 0.8;
+/// Pulses per second
+const PULSE_RATE: f32 = 2.0;
+const LINE_WIDTH: f32 = 3.0;
+const OUTLINE_WIDTH: f32 = 1.0;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Sector {
+    count: u32,
+    big: bool,
+    direction_sum: Vec2,
+}
+
+/// An arrow at the edge of the screen, pointing along `direction` from the middle of it
+fn arrow(
+    painter: &egui::Painter,
+    screen: egui::Rect,
+    direction: Vec2,
+    size: f32,
+    color: egui::Color32,
+) {
+    // World y points up, egui y points down
+    let direction = egui::vec2(direction.x, -direction.y).normalized();
+    let half_size = screen.shrink(EDGE_INSET).size() / 2.0;
+    let distance = (half_size.x / direction.x.abs()).min(half_size.y / direction.y.abs());
+    let tip = screen.center() + direction * distance;
+    let side = direction.rot90() * size * 0.6;
+    let back = tip - direction * size;
+    painter.add(egui::Shape::convex_polygon(
+        vec![tip, back + side, back - side],
+        color,
+        egui::Stroke::new(OUTLINE_WIDTH, egui::Color32::BLACK),
+    ));
+}
+
+fn draw_indicators(
+    mut egui_contexts: EguiContexts,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    enemy_query: Query<(&Transform, Option<&BigGhost>), With<Enemy>>,
+    spawn_state: Res<SpawnEnemiesState>,
+    accessibility: Res<AccessibilitySettings>,
+    time: Res<Time<Real>>,
+) {
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+    let camera_position = camera_transform.translation.truncate();
+    let view = Rect::from_center_size(camera_position, projection.area.size());
+
+    let ctx = egui_contexts.ctx_mut();
+    let screen = ctx.screen_rect();
+    let to_screen = |position: Vec2| {
+        let uv = (position - view.min) / view.size();
+        screen.lerp_inside(egui::vec2(uv.x, 1.0 - uv.y))
+    };
+    let painter = ctx.layer_painter(egui::LayerId::background());
+
+    let mut sectors = [Sector::default(); SECTORS];
+    for (transform, big_ghost) in enemy_query.iter() {
+        let position = transform.translation.truncate();
+        if view.contains(position) {
+            continue;
+        }
+        let direction = (position - camera_position).normalize_or_zero();
+        let angle = direction.y.atan2(direction.x).rem_euclid(TAU);
+        let sector = &mut sectors[(angle / TAU * SECTORS as f32) as usize % SECTORS];
+        sector.count += 1;
+        sector.big |= big_ghost.is_some();
+        sector.direction_sum += direction;
+    }
+
+    for sector in sectors.iter().filter(|s| s.count > 0) {
+        let size = ARROW_SIZE * (1.0 + (sector.count as f32).min(MAX_ARROW_GROUP).sqrt() / 2.0);
+        let (size, color) = if sector.big {
+            (size * 1.5, egui::Color32::from_rgb(255, 60, 60))
+        } else {
+            (size, egui::Color32::from_rgb(180, 100, 255))
+        };
+        arrow(&painter, screen, sector.direction_sum, size, color);
+    }
+
+    let Some(spawner) = spawn_state.next_spawner() else {
+        return;
+    };
+    let pulse = if accessibility.reduced_motion {
+        1.0
+    } else {
+        0.5 + 0.5 * (time.elapsed_seconds() * PULSE_RATE * TAU).sin()
+    };
+    let color = egui::Color32::from_rgba_unmultiplied(255, 200, 0, (100.0 + 155.0 * pulse) as u8);
+    if view.contains(spawner) {
+        let center = to_screen(spawner);
+        let radius = (to_screen(spawner + Vec2::X * SPAWNER_WARNING_RADIUS) - center).length();
+        painter.circle_stroke(
+            center,
+            radius * (0.8 + 0.2 * pulse),
+            egui::Stroke::new(LINE_WIDTH, color),
+        );
+    } else {
+        arrow(
+            &painter,
+            screen,
+            spawner - camera_position,
+            ARROW_SIZE * (1.0 + 0.3 * pulse),
+            color,
+        );
+    }
+}
+
+pub struct IndicatorsPlugin;
+
+impl Plugin for IndicatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            draw_indicators
+                .run_if(in_state(AppState::InGame).and_then(in_state(GameState::Playing))),
+        );
+    }
+}
@@ -0,0 +1,595 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+    utils::HashSet,
+    window::{CursorMoved, PrimaryWindow},
+};
+use bevy_egui::{egui, EguiContexts, EguiInput, EguiSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{camera::MainCamera, localization::Localization};
+
+/// Stick movement smaller than this is ignored, so worn sticks don't drift
+const STICK_DEADZONE: f32 = 0.2;
+
+/// Everything the player can do with a button. Aiming isn't a button, see `ActionState::aim_direction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Dash,
+    Pause,
+    /// Starting the game from the menus
+    Confirm,
+    /// Leaving the end screen
+    Back,
+    ToggleDebug,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Dash,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::ToggleDebug,
+    ];
+
+    pub fn name<'a>(&self, localization: &'a Localization) -> &'a str {
+        localization.get(match self {
+            Action::MoveUp => "action.move_up",
+            Action::MoveDown => "action.move_down",
+            Action::MoveLeft => "action.move_left",
+            Action::MoveRight => "action.move_right",
+            Action::Fire => "action.fire",
+            Action::Dash => "action.dash",
+            Action::Pause => "action.pause",
+            Action::Confirm => "action.confirm",
+            Action::Back => "action.back",
+            Action::ToggleDebug => "action.toggle_debug",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button on any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    pub fn name(&self, localization: &Localization) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(MouseButton::Left) => localization.get("binding.left_click").to_owned(),
+            Binding::Mouse(MouseButton::Right) => {
+                localization.get("binding.right_click").to_owned()
+            }
+            Binding::Mouse(MouseButton::Middle) => {
+                localization.get("binding.middle_click").to_owned()
+            }
+            Binding::Mouse(MouseButton::Other(n)) => {
+                localization.format("binding.mouse", &[("button", n)])
+            }
+            Binding::Gamepad(button) => format!("Pad {button:?}"),
+        }
+    }
+}
+
+/// Which buttons trigger which actions. Any number of bindings can trigger the same action.
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use Binding::*;
+        InputBindings {
+            bindings: BTreeMap::from([
+                (Action::MoveUp, vec![Key(KeyCode::W), Key(KeyCode::Up)]),
+                (Action::MoveDown, vec![Key(KeyCode::S), Key(KeyCode::Down)]),
+                (Action::MoveLeft, vec![Key(KeyCode::A), Key(KeyCode::Left)]),
+                (
+                    Action::MoveRight,
+                    vec![Key(KeyCode::D), Key(KeyCode::Right)],
+                ),
+                (
+                    Action::Fire,
+                    vec![
+                        Mouse(MouseButton::Left),
+                        Gamepad(GamepadButtonType::RightTrigger2),
+                    ],
+                ),
+                (
+                    Action::Dash,
+                    vec![
+                        Key(KeyCode::Space),
+                        Key(KeyCode::ShiftLeft),
+                        Gamepad(GamepadButtonType::LeftTrigger2),
+                    ],
+                ),
+                (
+                    Action::Pause,
+                    vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)],
+                ),
+                (
+                    Action::Confirm,
+                    vec![
+                        Key(KeyCode::Space),
+                        Key(KeyCode::Return),
+                        Gamepad(GamepadButtonType::South),
+                    ],
+                ),
+                (
+                    Action::Back,
+                    vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::East)],
+                ),
+                (Action::ToggleDebug, vec![Key(KeyCode::Backslash)]),
+            ]),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Name of the first button bound to `action`, for telling the player what to press
+    pub fn prompt(&self, action: Action, localization: &Localization) -> String {
+        match self.get(action).first() {
+            Some(binding) => binding.name(localization),
+            None => localization.get("binding.unbound").to_owned(),
+        }
+    }
+}
+
+/// The state of every action this frame. Systems should read this rather than raw input.
+#[derive(Debug, Default, Resource)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    just_released: HashSet<Action>,
+    /// Left stick, already past the deadzone
+    stick_movement: Vec2,
+    aim: Aim,
+}
+
+/// Whichever of the mouse or the right stick was used last
+#[derive(Debug, Clone, Copy)]
+enum Aim {
+    /// Where the cursor is, in world space
+    Cursor(Option<Vec2>),
+    /// Stays pointing the same way after the stick is let go
+    Stick(Vec2),
+}
+
+impl Default for Aim {
+    fn default() -> Self {
+        Aim::Cursor(None)
+    }
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Combines the movement actions into a direction with a length of at most 1
+    pub fn movement(&self) -> Vec2 {
+        let mut direction = Vec2::ZERO;
+        if self.pressed(Action::MoveLeft) {
+            direction.x -= 1.0;
+        }
+        if self.pressed(Action::MoveRight) {
+            direction.x += 1.0;
+        }
+        if self.pressed(Action::MoveUp) {
+            direction.y += 1.0;
+        }
+        if self.pressed(Action::MoveDown) {
+            direction.y -= 1.0;
+        }
+        (direction + self.stick_movement).clamp_length_max(1.0)
+    }
+
+    /// Returns the normalized direction from `from` towards wherever the player is aiming
+    pub fn aim_direction(&self, from: Vec2) -> Option<Vec2> {
+        match self.aim {
+            Aim::Cursor(target) => Some((target? - from).normalize_or_zero()),
+            Aim::Stick(direction) => Some(direction),
+        }
+    }
+
+    /// Holds `action` down for this frame, for on-screen controls that don't have a `Binding`
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    /// Presses and releases `action` within this frame
+    pub fn tap(&mut self, action: Action) {
+        self.just_pressed.insert(action);
+        self.just_released.insert(action);
+    }
+
+    /// Adds to the movement from the left stick, see `movement`
+    pub fn add_movement(&mut self, movement: Vec2) {
+        self.stick_movement += movement;
+    }
+
+    /// Aims in `direction` the same way the right stick does
+    pub fn aim_with_stick(&mut self, direction: Vec2) {
+        if direction != Vec2::ZERO {
+            self.aim = Aim::Stick(direction.normalize());
+        }
+    }
+}
+
+/// Runs once `ActionState` is up to date with this frame's bindings. Anything else that feeds into
+/// `ActionState` should run after this.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UpdateActions;
+
+/// Reads a stick, ignoring anything inside the deadzone and rescaling the rest to start from zero
+fn read_stick(
+    axes: &Axis<GamepadAxis>,
+    gamepad: Gamepad,
+    x: GamepadAxisType,
+    y: GamepadAxisType,
+) -> Vec2 {
+    let stick = Vec2::new(
+        axes.get(GamepadAxis::new(gamepad, x)).unwrap_or(0.0),
+        axes.get(GamepadAxis::new(gamepad, y)).unwrap_or(0.0),
+    );
+    let length = stick.length();
+    if length < STICK_DEADZONE {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0);
+    stick / length * scaled
+}
+
+fn update_action_state(
+    mut action_state: ResMut<ActionState>,
+    bindings: Res<InputBindings>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let action_state = &mut *action_state;
+    action_state.pressed.clear();
+    action_state.just_pressed.clear();
+    action_state.just_released.clear();
+
+    for action in Action::ALL {
+        let (mut pressed, mut just_pressed, mut just_released) = (false, false, false);
+        for binding in bindings.get(action) {
+            match binding {
+                Binding::Key(key) => {
+                    pressed |= keys.pressed(*key);
+                    just_pressed |= keys.just_pressed(*key);
+                    just_released |= keys.just_released(*key);
+                }
+                Binding::Mouse(button) => {
+                    pressed |= mouse_buttons.pressed(*button);
+                    just_pressed |= mouse_buttons.just_pressed(*button);
+                    just_released |= mouse_buttons.just_released(*button);
+                }
+                Binding::Gamepad(button_type) => {
+                    for gamepad in gamepads.iter() {
+                        let button = GamepadButton::new(gamepad, *button_type);
+                        pressed |= gamepad_buttons.pressed(button);
+                        just_pressed |= gamepad_buttons.just_pressed(button);
+                        just_released |= gamepad_buttons.just_released(button);
+                    }
+                }
+            }
+        }
+
+        if pressed {
+            action_state.pressed.insert(action);
+        }
+        if just_pressed {
+            action_state.just_pressed.insert(action);
+        }
+        // Only count as released once nothing is holding the action down anymore
+        if just_released && !pressed {
+            action_state.just_released.insert(action);
+        }
+    }
+
+    action_state.stick_movement = Vec2::ZERO;
+    let mut stick_aim = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        action_state.stick_movement += read_stick(
+            &gamepad_axes,
+            gamepad,
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+        );
+        stick_aim += read_stick(
+            &gamepad_axes,
+            gamepad,
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+        );
+    }
+
+    // Switch between the mouse and the right stick depending on which was touched last
+    if stick_aim != Vec2::ZERO {
+        action_state.aim = Aim::Stick(stick_aim.normalize());
+    } else if cursor_moved.read().count() > 0 {
+        action_state.aim = Aim::Cursor(None);
+    }
+    cursor_moved.clear();
+
+    if let Aim::Cursor(target) = &mut action_state.aim {
+        // The camera moves around, so this has to be worked out again every frame
+        *target = None;
+        let (Ok(window), Ok((camera, camera_transform))) =
+            (window_query.get_single(), camera_query.get_single())
+        else {
+            return;
+        };
+        if let Some(cursor_pos) = window.cursor_position() {
+            *target = camera.viewport_to_world_2d(camera_transform, cursor_pos);
+        }
+    }
+}
+
+/// Which binding is waiting for a new button to be pressed
+#[derive(Debug, Default, Resource)]
+pub struct Rebinding {
+    waiting: Option<(Action, Option<usize>)>,
+    /// Set a frame after waiting starts, so the press that started it can't end up bound
+    armed: bool,
+}
+
+impl Rebinding {
+    fn start(&mut self, action: Action, slot: Option<usize>) {
+        self.waiting = Some((action, slot));
+        self.armed = false;
+    }
+}
+
+fn capture_rebinding(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<InputBindings>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse_buttons: ResMut<Input<MouseButton>>,
+    mut gamepad_buttons: ResMut<Input<GamepadButton>>,
+    mut egui_contexts: EguiContexts,
+) {
+    let Some((action, slot)) = rebinding.waiting else {
+        return;
+    };
+    if !rebinding.armed {
+        rebinding.armed = true;
+        return;
+    }
+
+    // Clicks on the menu are for its buttons, like Cancel, not for binding
+    let pointer_over_menu = egui_contexts.ctx_mut().is_pointer_over_area();
+    let key = keys.get_just_pressed().next().copied();
+    let mouse_button = mouse_buttons
+        .get_just_pressed()
+        .next()
+        .copied()
+        .filter(|_| !pointer_over_menu);
+    let gamepad_button = gamepad_buttons.get_just_pressed().next().copied();
+    // Whatever gets pressed is used up here, so it doesn't also do what it's bound to
+    let binding = if let Some(key) = key {
+        keys.clear_just_pressed(key);
+        Binding::Key(key)
+    } else if let Some(button) = mouse_button {
+        mouse_buttons.clear_just_pressed(button);
+        Binding::Mouse(button)
+    } else if let Some(button) = gamepad_button {
+        gamepad_buttons.clear_just_pressed(button);
+        Binding::Gamepad(button.button_type)
+    } else {
+        return;
+    };
+    rebinding.waiting = None;
+
+    // Escape and Select back out instead of being bound, for keyboards and gamepads, which can't
+    // get to the Cancel button without pressing something that would be bound
+    if matches!(
+        binding,
+        Binding::Key(KeyCode::Escape) | Binding::Gamepad(GamepadButtonType::Select)
+    ) {
+        return;
+    }
+
+    let action_bindings = bindings.bindings.entry(action).or_default();
+    // Binding something the action already has would only make a duplicate
+    if !action_bindings.contains(&binding) {
+        match slot {
+            Some(i) if i < action_bindings.len() => action_bindings[i] = binding,
+            _ => action_bindings.push(binding),
+        }
+    }
+}
+
+/// Lists the bindings for every action. Click a binding to change it, then press the new button,
+/// or Escape or Select to cancel. The button next to each binding removes it, as does right
+/// clicking it.
+///
+/// Returns `true` if any bindings changed.
+pub fn controls_ui(
+    ui: &mut egui::Ui,
+    bindings: &mut InputBindings,
+    rebinding: &mut Rebinding,
+    localization: &Localization,
+) -> bool {
+    let mut changed = false;
+
+    egui::Grid::new("controls").show(ui, |ui| {
+        for action in Action::ALL {
+            ui.label(action.name(localization));
+            ui.horizontal(|ui| {
+                let action_bindings = bindings.bindings.entry(action).or_default();
+                let mut remove = None;
+                for (i, binding) in action_bindings.iter().enumerate() {
+                    let text = if rebinding.waiting == Some((action, Some(i))) {
+                        "...".to_owned()
+                    } else {
+                        binding.name(localization)
+                    };
+                    let response = ui.button(text);
+                    if response.clicked() {
+                        rebinding.start(action, Some(i));
+                    }
+                    // A button of its own so touch screens and gamepads can remove bindings too
+                    let clear = ui
+                        .small_button("×")
+                        .on_hover_text(localization.get("controls.clear"));
+                    if response.secondary_clicked() || clear.clicked() {
+                        remove = Some(i);
+                    }
+                }
+
+                let text = if rebinding.waiting == Some((action, None)) {
+                    "..."
+                } else {
+                    "+"
+                };
+                if ui.button(text).clicked() {
+                    rebinding.start(action, None);
+                }
+
+                if rebinding
+                    .waiting
+                    .is_some_and(|(waiting, _)| waiting == action)
+                    && ui.button(localization.get("controls.cancel")).clicked()
+                {
+                    rebinding.waiting = None;
+                }
+
+                if let Some(i) = remove {
+                    action_bindings.remove(i);
+                    rebinding.waiting = None;
+                    changed = true;
+                }
+            });
+            ui.end_row();
+        }
+    });
+
+    if ui.button(localization.get("controls.reset")).clicked() {
+        *bindings = InputBindings::default();
+        rebinding.waiting = None;
+        changed = true;
+    }
+
+    changed
+}
+
+/// Lets egui menus be used with a gamepad by pretending the D-pad is the keyboard.
+///
+/// Up and down move focus through the widgets in order, left and right nudge sliders, and the
+/// bottom face button clicks whatever has focus.
+fn gamepad_menu_navigation(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut egui_input_query: Query<&mut EguiInput, With<PrimaryWindow>>,
+) {
+    let Ok(mut egui_input) = egui_input_query.get_single_mut() else {
+        return;
+    };
+
+    for gamepad in gamepads.iter() {
+        for (button_type, key, modifiers) in [
+            (
+                GamepadButtonType::DPadUp,
+                egui::Key::Tab,
+                egui::Modifiers::SHIFT,
+            ),
+            (
+                GamepadButtonType::DPadDown,
+                egui::Key::Tab,
+                egui::Modifiers::NONE,
+            ),
+            (
+                GamepadButtonType::DPadLeft,
+                egui::Key::ArrowLeft,
+                egui::Modifiers::NONE,
+            ),
+            (
+                GamepadButtonType::DPadRight,
+                egui::Key::ArrowRight,
+                egui::Modifiers::NONE,
+            ),
+            (
+                GamepadButtonType::South,
+                egui::Key::Enter,
+                egui::Modifiers::NONE,
+            ),
+        ] {
+            let button = GamepadButton::new(gamepad, button_type);
+            for (pressed, happened) in [
+                (true, gamepad_buttons.just_pressed(button)),
+                (false, gamepad_buttons.just_released(button)),
+            ] {
+                if happened {
+                    egui_input.events.push(egui::Event::Key {
+                        key,
+                        pressed,
+                        repeat: false,
+                        modifiers,
+                    });
+                }
+            }
+        }
+    }
+}
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputBindings>()
+            .init_resource::<ActionState>()
+            .init_resource::<Rebinding>()
+            .add_systems(
+                PreUpdate,
+                (capture_rebinding, update_action_state)
+                    .chain()
+                    .in_set(UpdateActions)
+                    .after(InputSystem),
+            )
+            .add_systems(
+                PreUpdate,
+                gamepad_menu_navigation
+                    .after(EguiSet::ProcessInput)
+                    .before(EguiSet::BeginFrame),
+            );
+    }
+}
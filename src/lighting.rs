@@ -0,0 +1,244 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState,
+            RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+    sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle},
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    camera::MainCamera,
+    devices::fireball::Fireball,
+    difficulty::Difficulty,
+    experience::ExperienceOrb,
+    health_orb::HealthOrb,
+    map::{Wall, MAP_SCALE, MAP_SIZE},
+    physics,
+    player::Player,
+    states::AppState,
+};
+
+/// These have to match the array sizes in `lighting.wgsl`
+const MAX_LIGHTS: usize = 32;
+const MAX_WALLS: usize = 16;
+
+/// Drawn over the map and characters, which stay within 0.1 of zero, but under enemy outlines and
+/// health bars, fireballs, orbs and particles, from 0.9 up, which are easier to read at full
+/// brightness and look like they're glowing anyway. Outlines and health bars are children of their
+/// enemy, so they undo the enemy's scale to end up that high.
+const LIGHTING_Z: f32 = 0.5;
+
+/// How bright it is on the first night, and how dark it gets after many nights
+const FIRST_NIGHT_BRIGHTNESS: f32 = 0.45;
+const DARKEST_BRIGHTNESS: f32 = 0.12;
+/// How much of the gap to `DARKEST_BRIGHTNESS` is left after each night
+const DARKENING_PER_NIGHT: f32 = 0.85;
+
+const PLAYER_LIGHT_RADIUS: f32 = 4.5;
+const FIREBALL_LIGHT_RADIUS: f32 = 1.5;
+const ORB_LIGHT_RADIUS: f32 = 0.6;
+
+/// A point light, with brightness falling off to nothing at `radius`
+#[derive(Debug, Clone, Copy)]
+struct Light {
+    color: Color,
+    position: Vec2,
+    radius: f32,
+    /// Only the player's light is blocked by walls, since checking every wall for every light on
+    /// every pixel adds up
+    casts_shadows: bool,
+}
+
+/// Darkens everything drawn under it by multiplying it with the ambient light plus whatever lights
+/// reach each pixel
+#[derive(Asset, TypePath, AsBindGroup, Debug, Default, Clone)]
+pub struct LightingMaterial {
+    #[uniform(0)]
+    ambient: Color,
+    #[uniform(0)]
+    light_colors: [Color; MAX_LIGHTS],
+    /// Position of each light in xy, its radius in z, and 1 in w if it casts shadows
+    #[uniform(0)]
+    lights: [Vec4; MAX_LIGHTS],
+    #[uniform(0)]
+    light_count: u32,
+    /// Bounding boxes of the walls, as min.xy and max.xy
+    #[uniform(0)]
+    walls: [Vec4; MAX_WALLS],
+    #[uniform(0)]
+    wall_count: u32,
+}
+
+impl Material2d for LightingMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/lighting.wgsl".into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Multiply the color already drawn by the light, keeping its alpha. Plain blending like
+        // this works on WebGL2, unlike a separate render target for the light.
+        if let Some(target) = descriptor
+            .fragment
+            .as_mut()
+            .and_then(|fragment| fragment.targets[0].as_mut())
+        {
+            target.blend = Some(BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::Dst,
+                    dst_factor: BlendFactor::Zero,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent {
+                    src_factor: BlendFactor::Zero,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Component)]
+struct LightingOverlay;
+
+/// Ambient light for `night`, a dim moonlight blue that gets darker each night
+fn ambient_light(night: u32) -> Color {
+    let brightness = DARKEST_BRIGHTNESS
+        + (FIRST_NIGHT_BRIGHTNESS - DARKEST_BRIGHTNESS)
+            * DARKENING_PER_NIGHT.powi(night.saturating_sub(1) as i32);
+    Color::rgb(0.7 * brightness, 0.8 * brightness, brightness)
+}
+
+fn setup_lighting(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<LightingMaterial>>,
+) {
+    // Big enough to cover the view wherever the camera goes, even zoomed out at the map's edges
+    let size = 3.0 * MAP_SIZE * MAP_SCALE * physics::PHYSICS_SCALE;
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes
+                .add(shape::Quad::new(Vec2::splat(size)).into())
+                .into(),
+            material: materials.add(LightingMaterial {
+                ambient: ambient_light(1),
+                ..Default::default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, LIGHTING_Z),
+            ..Default::default()
+        },
+        LightingOverlay,
+    ));
+}
+
+fn cleanup_lighting(mut commands: Commands, query: Query<Entity, With<LightingOverlay>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+fn update_lighting(
+    overlay_query: Query<&Handle<LightingMaterial>, With<LightingOverlay>>,
+    mut materials: ResMut<Assets<LightingMaterial>>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    player_query: Query<&Transform, With<Player>>,
+    fireball_query: Query<&Transform, With<Fireball>>,
+    experience_orb_query: Query<&Transform, With<ExperienceOrb>>,
+    health_orb_query: Query<&Transform, With<HealthOrb>>,
+    wall_query: Query<(&Transform, &Collider), With<Wall>>,
+    difficulty: Res<Difficulty>,
+) {
+    let Some(material) = overlay_query
+        .get_single()
+        .ok()
+        .and_then(|handle| materials.get_mut(handle))
+    else {
+        return;
+    };
+    let camera_position = camera_query
+        .get_single()
+        .map(|t| t.translation.truncate())
+        .unwrap_or_default();
+
+    let light = |transform: &Transform, color: Color, radius: f32| Light {
+        color,
+        position: transform.translation.truncate(),
+        radius,
+        casts_shadows: false,
+    };
+    let mut lights: Vec<Light> = fireball_query
+        .iter()
+        .map(|t| light(t, Color::rgb(1.0, 0.55, 0.15), FIREBALL_LIGHT_RADIUS))
+        .chain(
+            experience_orb_query
+                .iter()
+                .map(|t| light(t, Color::rgb(0.3, 0.9, 0.4), ORB_LIGHT_RADIUS)),
+        )
+        .chain(
+            health_orb_query
+                .iter()
+                .map(|t| light(t, Color::rgb(0.9, 0.25, 0.25), ORB_LIGHT_RADIUS)),
+        )
+        .collect();
+    // When there are too many lights, keep the ones nearest the middle of the screen
+    lights.sort_by(|a, b| {
+        a.position
+            .distance_squared(camera_position)
+            .total_cmp(&b.position.distance_squared(camera_position))
+    });
+    for transform in player_query.iter() {
+        lights.insert(
+            0,
+            Light {
+                casts_shadows: true,
+                ..light(transform, Color::rgb(1.0, 0.85, 0.6), PLAYER_LIGHT_RADIUS)
+            },
+        );
+    }
+    lights.truncate(MAX_LIGHTS);
+
+    let walls: Vec<Vec4> = wall_query
+        .iter()
+        .filter_map(|(transform, collider)| {
+            let half_extents = collider.as_cuboid()?.half_extents();
+            let center = transform.translation.truncate();
+            let (min, max) = (center - half_extents, center + half_extents);
+            Some(Vec4::new(min.x, min.y, max.x, max.y))
+        })
+        .take(MAX_WALLS)
+        .collect();
+
+    material.ambient = ambient_light(difficulty.night);
+    material.light_count = lights.len() as u32;
+    for (i, light) in lights.iter().enumerate() {
+        material.light_colors[i] = light.color;
+        material.lights[i] = light
+            .position
+            .extend(light.radius)
+            .extend(if light.casts_shadows { 1.0 } else { 0.0 });
+    }
+    material.wall_count = walls.len() as u32;
+    material.walls[..walls.len()].copy_from_slice(&walls);
+}
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<LightingMaterial>::default())
+            .add_systems(OnEnter(AppState::InGame), setup_lighting)
+            .add_systems(OnExit(AppState::InGame), cleanup_lighting)
+            .add_systems(Update, update_lighting.run_if(in_state(AppState::InGame)));
+    }
+}
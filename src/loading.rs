@@ -1,7 +1,15 @@
-use bevy::{asset::UntypedAssetId, prelude::*, render::texture::ImageSampler};
+use bevy::{
+    asset::{LoadState, UntypedAssetId},
+    prelude::*,
+    render::texture::ImageSampler,
+};
 use bevy_egui::EguiContexts;
 
-use crate::{healthbar::HealthbarMaterial, states::AppState};
+use crate::{
+    accessibility::AccessibilitySettings,
+    healthbar::{BarKind, HealthbarMaterial},
+    states::AppState,
+};
 
 #[derive(Resource, Debug, Default)]
 pub struct LoadingAssets {
@@ -19,20 +27,37 @@ struct LoadingBar {
     material: Handle<HealthbarMaterial>,
 }
 
+/// The font Bevy UI text should use, which depends on the language, see `localization.rs`
 #[derive(Debug, Default, Resource)]
 pub struct GlobalFont(pub Handle<Font>);
 
+/// Every font the game comes with
+#[derive(Debug, Default, Resource)]
+pub struct Fonts {
+    pub main: Handle<Font>,
+    /// Covers scripts `main` doesn't, at the cost of looking plainer
+    pub fallback: Handle<Font>,
+}
+
 fn load_font(
     mut commands: Commands,
     mut fonts: ResMut<Assets<Font>>,
     mut egui_contexts: EguiContexts,
 ) {
     let font = include_bytes!("../assets/fonts/Tuffy_Bold.ttf");
+    let fallback_font = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
     debug!("font: {:?}", font.len());
     let bevy_font = Font::try_from_bytes(font.to_vec()).expect("Failed to load font");
     let handle = fonts.add(bevy_font);
-
-    commands.insert_resource(GlobalFont(handle));
+    let bevy_fallback_font =
+        Font::try_from_bytes(fallback_font.to_vec()).expect("Failed to load font");
+    let fallback_handle = fonts.add(bevy_fallback_font);
+
+    commands.insert_resource(GlobalFont(handle.clone()));
+    commands.insert_resource(Fonts {
+        main: handle,
+        fallback: fallback_handle,
+    });
 
     let mut fonts = bevy_egui::egui::FontDefinitions::default();
 
@@ -40,13 +65,19 @@ fn load_font(
         "Tuffy Bold".to_owned(),
         bevy_egui::egui::FontData::from_static(font),
     );
+    fonts.font_data.insert(
+        "DejaVu Sans Bold".to_owned(),
+        bevy_egui::egui::FontData::from_static(fallback_font),
+    );
 
-    // Insert the new font as the highest priority proportional font
-    fonts
+    // Insert the new font as the highest priority proportional font, egui looks through the rest
+    // in order for any characters it's missing
+    let proportional = fonts
         .families
         .entry(bevy_egui::egui::FontFamily::Proportional)
-        .or_default()
-        .insert(0, "Tuffy Bold".to_owned());
+        .or_default();
+    proportional.insert(0, "Tuffy Bold".to_owned());
+    proportional.insert(1, "DejaVu Sans Bold".to_owned());
 
     egui_contexts.ctx_mut().set_fonts(fonts);
 }
@@ -82,10 +113,18 @@ fn load_assets(
     }
 
     for (is_loaded, asset) in unloaded.iter_mut() {
-        if !*is_loaded && asset_server.is_loaded_with_dependencies(*asset) {
+        if *is_loaded {
+            continue;
+        }
+        if asset_server.is_loaded_with_dependencies(*asset) {
             debug!("Asset loaded!");
             *is_loaded = true;
             *loaded_assets += 1;
+        } else if asset_server.get_load_state(*asset) == Some(LoadState::Failed) {
+            // Don't get stuck on the loading screen forever, whatever needed it will have to cope
+            warn!("Failed to load asset {:?}", asset);
+            *is_loaded = true;
+            *loaded_assets += 1;
         }
     }
 
@@ -102,11 +141,14 @@ fn load_assets(
 #[derive(Component, Debug, Default)]
 struct LoadingScreenMarker;
 
-fn setup_loading_bar(mut commands: Commands, mut materials: ResMut<Assets<HealthbarMaterial>>) {
+fn setup_loading_bar(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<HealthbarMaterial>>,
+    accessibility: Res<AccessibilitySettings>,
+) {
     let material = materials.add(HealthbarMaterial {
-        filled_color: Color::LIME_GREEN,
-        empty_color: Color::GRAY,
         fraction: 0.0,
+        ..HealthbarMaterial::new(BarKind::Experience, accessibility.palette)
     });
 
     commands.spawn((
@@ -0,0 +1,313 @@
+use std::fmt::Display;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::{BoxedFuture, HashMap},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::loading::{Fonts, GlobalFont, LoadingAssets};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    Spanish,
+    French,
+    Russian,
+    Ukrainian,
+}
+
+impl Language {
+    pub const ALL: [Language; 6] = [
+        Language::English,
+        Language::German,
+        Language::Spanish,
+        Language::French,
+        Language::Russian,
+        Language::Ukrainian,
+    ];
+
+    /// Names the string table in `assets/locale`
+    fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::Spanish => "es",
+            Language::French => "fr",
+            Language::Russian => "ru",
+            Language::Ukrainian => "uk",
+        }
+    }
+
+    /// What the language calls itself, so people can find theirs whatever language is picked
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::Spanish => "Español",
+            Language::French => "Français",
+            Language::Russian => "Русский",
+            Language::Ukrainian => "Українська",
+        }
+    }
+
+    /// Which plural form goes with `n`, following the CLDR rules for whole numbers
+    pub fn plural_category(&self, n: u32) -> PluralCategory {
+        match self {
+            Language::English | Language::German | Language::Spanish => {
+                if n == 1 {
+                    PluralCategory::One
+                } else {
+                    PluralCategory::Other
+                }
+            }
+            Language::French => {
+                if n <= 1 {
+                    PluralCategory::One
+                } else {
+                    PluralCategory::Other
+                }
+            }
+            Language::Russian | Language::Ukrainian => {
+                if n % 10 == 1 && n % 100 != 11 {
+                    PluralCategory::One
+                } else if (2..=4).contains(&(n % 10)) && !(12..=14).contains(&(n % 100)) {
+                    PluralCategory::Few
+                } else {
+                    PluralCategory::Many
+                }
+            }
+        }
+    }
+
+    /// Tuffy is missing some letters of these, so they're drawn with DejaVu Sans instead
+    fn needs_fallback_font(&self) -> bool {
+        matches!(self, Language::Ukrainian)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    One,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    fn suffix(&self) -> &'static str {
+        match self {
+            PluralCategory::One => "one",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// Every bit of text the game shows in one language, loaded from `assets/locale/<code>.strings`.
+///
+/// Each line is `key = text`, with `\n` for line breaks and `{name}` where values get filled in.
+/// Text that depends on a number has a line per plural form, like `key.one` and `key.other`.
+#[derive(Asset, TypePath, Debug, Default, Clone)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+}
+
+impl StringTable {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    fn plural(&self, key: &str, category: PluralCategory) -> Option<&str> {
+        self.get(&format!("{key}.{}", category.suffix()))
+            .or_else(|| self.get(&format!("{key}.other")))
+    }
+}
+
+#[derive(Default)]
+struct StringTableLoader;
+
+impl AssetLoader for StringTableLoader {
+    type Asset = StringTable;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut text = String::new();
+            reader.read_to_string(&mut text).await?;
+
+            let mut table = StringTable::default();
+            for line in text.lines().map(str::trim) {
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let Some((key, value)) = line.split_once('=') else {
+                    warn!(
+                        "Line without a key in {}, ignoring it: {line}",
+                        load_context.path().display()
+                    );
+                    continue;
+                };
+                table
+                    .strings
+                    .insert(key.trim().to_owned(), value.trim().replace("\\n", "\n"));
+            }
+
+            Ok(table)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["strings"]
+    }
+}
+
+#[derive(Debug, Default, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageSettings {
+    pub language: Language,
+}
+
+/// Looks up text in the current language, falling back to English and then to the key itself
+/// for anything that hasn't been translated yet.
+#[derive(Debug, Default, Resource)]
+pub struct Localization {
+    language: Language,
+    current: StringTable,
+    english: StringTable,
+}
+
+impl Localization {
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.current
+            .get(key)
+            .or_else(|| self.english.get(key))
+            .unwrap_or(key)
+    }
+
+    /// Text with each `{name}` replaced by the value given for it
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        fill(self.get(key), args)
+    }
+
+    /// Like `format`, but picks the plural form that goes with `count`, which also fills `{count}`
+    pub fn plural(&self, key: &str, count: u32, args: &[(&str, &dyn Display)]) -> String {
+        let text = self
+            .current
+            .plural(key, self.language.plural_category(count))
+            .or_else(|| {
+                self.english
+                    .plural(key, Language::English.plural_category(count))
+            })
+            .unwrap_or(key);
+        let mut args = args.to_vec();
+        args.push(("count", &count));
+        fill(text, &args)
+    }
+}
+
+fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = text.to_owned();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+#[derive(Debug, Default, Resource)]
+struct StringTables {
+    tables: HashMap<Language, Handle<StringTable>>,
+}
+
+fn load_string_tables(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+) {
+    // They're tiny, so load all of them up front to switch between them instantly
+    let tables = Language::ALL
+        .into_iter()
+        .map(|language| {
+            let table =
+                asset_server.load::<StringTable>(format!("locale/{}.strings", language.code()));
+            loading_assets.add(table.clone());
+            (language, table)
+        })
+        .collect();
+
+    commands.insert_resource(StringTables { tables });
+}
+
+fn update_localization(
+    settings: Res<LanguageSettings>,
+    string_tables: Res<StringTables>,
+    tables: Res<Assets<StringTable>>,
+    mut events: EventReader<AssetEvent<StringTable>>,
+    mut localization: ResMut<Localization>,
+) {
+    // Tables change when they finish loading, or when they're edited with hot reloading on
+    let tables_changed = events
+        .read()
+        .any(|ev| matches!(ev, AssetEvent::Added { .. } | AssetEvent::Modified { .. }));
+    if !settings.is_changed() && !tables_changed {
+        return;
+    }
+
+    let table = |language: Language| {
+        string_tables
+            .tables
+            .get(&language)
+            .and_then(|handle| tables.get(handle))
+            .cloned()
+            .unwrap_or_default()
+    };
+    *localization = Localization {
+        language: settings.language,
+        current: table(settings.language),
+        english: table(Language::English),
+    };
+}
+
+fn update_global_font(
+    settings: Res<LanguageSettings>,
+    fonts: Res<Fonts>,
+    mut global_font: ResMut<GlobalFont>,
+) {
+    if !settings.is_changed() && !fonts.is_changed() {
+        return;
+    }
+    global_font.0 = if settings.language.needs_fallback_font() {
+        fonts.fallback.clone()
+    } else {
+        fonts.main.clone()
+    };
+}
+
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<StringTable>()
+            .init_asset_loader::<StringTableLoader>()
+            .init_resource::<LanguageSettings>()
+            .init_resource::<Localization>()
+            .add_systems(Startup, load_string_tables)
+            .add_systems(
+                PreUpdate,
+                (
+                    update_localization,
+                    update_global_font.run_if(resource_exists::<Fonts>()),
+                ),
+            );
+    }
+}
@@ -2,9 +2,13 @@
 
 use bevy::{asset::AssetMetaCheck, log::LogPlugin, prelude::*};
 
+mod accessibility;
+mod animation;
 mod audio;
+mod auto_aim;
 mod camera;
 mod character;
+mod dash;
 mod debug;
 mod devices;
 mod difficulty;
@@ -12,15 +16,29 @@ mod end;
 mod enemy;
 mod experience;
 mod health;
+mod health_orb;
 mod healthbar;
+mod hit_feedback;
+mod hud;
+mod indicators;
+mod input;
+mod lighting;
 mod loading;
+mod localization;
 mod main_menu;
 mod map;
+mod minimap;
+mod particles;
 mod pathfinding;
 mod pause_menu;
 mod physics;
 mod player;
+mod settings;
+mod sfx;
 mod states;
+mod status;
+mod storage;
+mod touch;
 mod ui;
 
 fn main() {
@@ -57,14 +75,25 @@ fn main() {
             end::EndPlugin,
             pause_menu::PauseMenuPlugin,
             audio::AudioPlugin,
+            health_orb::HealthOrbPlugin,
+            status::StatusPlugin,
+            dash::DashPlugin,
+            hit_feedback::HitFeedbackPlugin,
+            animation::AnimationPlugin,
+            particles::ParticlesPlugin,
+            sfx::SfxPlugin,
+            settings::SettingsPlugin,
+            input::InputPlugin,
+            auto_aim::AutoAimPlugin,
+        ))
+        .add_plugins((
+            touch::TouchPlugin,
+            accessibility::AccessibilityPlugin,
+            localization::LocalizationPlugin,
+            hud::HudPlugin,
+            minimap::MinimapPlugin,
+            indicators::IndicatorsPlugin,
+            lighting::LightingPlugin,
         ))
-        .add_systems(Startup, setup)
         .run();
 }
-
-fn setup(mut commands: Commands) {
-    commands.insert_resource(AmbientLight {
-        brightness: 1.0,
-        color: Color::WHITE,
-    });
-}
@@ -2,7 +2,10 @@ use bevy::prelude::*;
 
 use crate::{
     devices::fireball_upgrades::FinishedUpgrading,
+    input::{Action, ActionState, InputBindings},
     loading::{GlobalFont, LoadingAssets},
+    localization::Localization,
+    settings::SettingsScreen,
     states::AppState,
 };
 
@@ -25,6 +28,9 @@ fn load_main_menu_assets(
 #[derive(Debug, Default, Component)]
 struct MainMenuMarker;
 
+#[derive(Debug, Default, Component)]
+struct StartText;
+
 fn setup_main_menu(
     mut commands: Commands,
     main_menu_assets: Res<MainMenuAssets>,
@@ -65,25 +71,54 @@ fn setup_main_menu(
                 },
                 ..Default::default()
             });
-            parent.spawn(TextBundle {
-                style: Style {
-                    bottom: Val::Percent(10.0),
-                    position_type: PositionType::Absolute,
-                    ..Default::default()
-                },
-                text: Text::from_section(
-                    "Press SPACE to start",
-                    TextStyle {
-                        font: global_font.0.clone(),
-                        font_size: 36.0,
+            parent.spawn((
+                TextBundle {
+                    style: Style {
+                        bottom: Val::Percent(10.0),
+                        position_type: PositionType::Absolute,
                         ..Default::default()
                     },
-                ),
-                ..Default::default()
-            });
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: global_font.0.clone(),
+                            font_size: 36.0,
+                            ..Default::default()
+                        },
+                    ),
+                    ..Default::default()
+                },
+                StartText,
+            ));
         });
 }
 
+/// The language and bindings can both be changed from the settings screen on top of the main
+/// menu, so keep the prompt up to date with them
+fn update_start_text(
+    mut query: Query<&mut Text, With<StartText>>,
+    added_query: Query<(), Added<StartText>>,
+    localization: Res<Localization>,
+    bindings: Res<InputBindings>,
+    global_font: Res<GlobalFont>,
+) {
+    if added_query.is_empty()
+        && !localization.is_changed()
+        && !bindings.is_changed()
+        && !global_font.is_changed()
+    {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        let section = &mut text.sections[0];
+        section.value = localization.format(
+            "main_menu.start",
+            &[("key", &bindings.prompt(Action::Confirm, &localization))],
+        );
+        section.style.font = global_font.0.clone();
+    }
+}
+
 fn cleanup_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuMarker>>) {
     for e in query.iter() {
         commands.entity(e).despawn_recursive();
@@ -92,10 +127,11 @@ fn cleanup_main_menu(mut commands: Commands, query: Query<Entity, With<MainMenuM
 
 fn handle_start(
     mut next_state: ResMut<NextState<AppState>>,
-    input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
     mut writer: EventWriter<FinishedUpgrading>,
+    settings_screen: Res<SettingsScreen>,
 ) {
-    if input.just_released(KeyCode::Space) {
+    if actions.just_released(Action::Confirm) && !settings_screen.open {
         // a bit of a hack to get restarts working, see `next_night_delay` in `difficulty.rs`
         writer.send(FinishedUpgrading);
         info!("Sent event so restarting works");
@@ -110,6 +146,9 @@ impl Plugin for MainMenuPlugin {
         app.add_systems(Startup, load_main_menu_assets)
             .add_systems(OnEnter(AppState::MainMenu), setup_main_menu)
             .add_systems(OnExit(AppState::MainMenu), cleanup_main_menu)
-            .add_systems(Update, handle_start.run_if(in_state(AppState::MainMenu)));
+            .add_systems(
+                Update,
+                (handle_start, update_start_text).run_if(in_state(AppState::MainMenu)),
+            );
     }
 }
@@ -10,8 +10,8 @@ use crate::{debug::DebugOverlay, loading::LoadingAssets, physics, states::AppSta
 pub struct MapPlugin;
 
 #[derive(Debug, Default, Resource)]
-struct MapAssets {
-    texture: Handle<Image>,
+pub struct MapAssets {
+    pub texture: Handle<Image>,
 }
 
 fn load_map_assets(
@@ -0,0 +1,121 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    enemy::Enemy,
+    map::{EnemySpawner, MapAssets, Region, MAP_SCALE, MAP_SIZE},
+    physics,
+    player::Player,
+    states::AppState,
+};
+
+/// Width and height of the minimap, in egui points so it follows the UI scale
+const MINIMAP_SIZE: f32 = 200.0;
+const MARGIN: f32 = 10.0;
+const DOT_RADIUS: f32 = 2.5;
+/// Enemies are grouped into this many cells across the map, and drawn as one dot per cell that
+/// grows with the number of enemies in it. Otherwise big waves turn into a purple smear.
+const ENEMY_CELLS: f32 = 32.0;
+/// Enemy dots stop growing past this many enemies
+const MAX_ENEMY_GROUP: f32 = 16.0;
+const LINE_WIDTH: f32 = 1.0;
+
+fn draw_minimap(
+    mut egui_contexts: EguiContexts,
+    map_assets: Res<MapAssets>,
+    player_query: Query<&Transform, With<Player>>,
+    enemy_query: Query<&Transform, With<Enemy>>,
+    spawner_query: Query<&Transform, With<EnemySpawner>>,
+    region_query: Query<&Region>,
+) {
+    let map_texture = egui_contexts.add_image(map_assets.texture.clone_weak());
+
+    // The map is centered on the origin, see `setup_map`
+    let half_extent = MAP_SIZE / 2.0 * physics::PHYSICS_SCALE * MAP_SCALE;
+    let player_color = egui::Color32::from_rgb(255, 140, 0);
+    let enemy_color = egui::Color32::from_rgb(180, 100, 255);
+    let spawner_color = egui::Color32::from_rgb(110, 40, 160);
+    let region_color = egui::Color32::from_rgba_unmultiplied(0, 128, 128, 160);
+
+    egui::Area::new("minimap")
+        .anchor(
+            egui::Align2::RIGHT_BOTTOM,
+            egui::Vec2::new(-MARGIN, -MARGIN),
+        )
+        .order(egui::Order::Background)
+        .interactable(false)
+        .show(egui_contexts.ctx_mut(), |ui| {
+            let (response, painter) =
+                ui.allocate_painter(egui::Vec2::splat(MINIMAP_SIZE), egui::Sense::hover());
+            let rect = response.rect;
+            let to_minimap = |position: Vec2| {
+                let uv = (position + half_extent) / (2.0 * half_extent);
+                // World y points up, egui y points down
+                rect.lerp_inside(egui::vec2(uv.x, 1.0 - uv.y))
+            };
+
+            painter.image(
+                map_texture,
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                egui::Color32::WHITE,
+            );
+            painter.rect_stroke(
+                rect,
+                0.0,
+                egui::Stroke::new(LINE_WIDTH, egui::Color32::BLACK),
+            );
+
+            for region in region_query.iter() {
+                painter.rect_stroke(
+                    egui::Rect::from_two_pos(
+                        to_minimap(region.area.min),
+                        to_minimap(region.area.max),
+                    ),
+                    0.0,
+                    egui::Stroke::new(LINE_WIDTH, region_color),
+                );
+            }
+
+            for transform in spawner_query.iter() {
+                painter.circle_stroke(
+                    to_minimap(transform.translation.truncate()),
+                    DOT_RADIUS * 2.0,
+                    egui::Stroke::new(LINE_WIDTH, spawner_color),
+                );
+            }
+
+            let cell_size = 2.0 * half_extent / ENEMY_CELLS;
+            let mut cells: HashMap<IVec2, (Vec2, u32)> = HashMap::default();
+            for transform in enemy_query.iter() {
+                let position = transform.translation.truncate();
+                let cell = cells
+                    .entry((position / cell_size).floor().as_ivec2())
+                    .or_default();
+                cell.0 += position;
+                cell.1 += 1;
+            }
+            for (sum, count) in cells.into_values() {
+                let radius = DOT_RADIUS * (count as f32).min(MAX_ENEMY_GROUP).sqrt();
+                painter.circle_filled(to_minimap(sum / count as f32), radius, enemy_color);
+            }
+
+            for transform in player_query.iter() {
+                let position = to_minimap(transform.translation.truncate());
+                painter.circle_filled(position, DOT_RADIUS * 1.5, player_color);
+                painter.circle_stroke(
+                    position,
+                    DOT_RADIUS * 1.5,
+                    egui::Stroke::new(LINE_WIDTH, egui::Color32::BLACK),
+                );
+            }
+        });
+}
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_minimap.run_if(in_state(AppState::InGame)));
+    }
+}
@@ -0,0 +1,234 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::states::AppState;
+
+/// Describes what particles look like and how they move. Shared by emitters and one-off bursts.
+///
+/// Everything is simulated on the CPU with plain sprites, so it works the same on the wasm build.
+#[derive(Debug, Clone)]
+pub struct ParticleEffect {
+    /// How long each particle lives, in seconds
+    pub lifetime: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Direction particles are launched in, before `spread` is applied
+    pub direction: Vec2,
+    /// Maximum angle in radians particles can deviate from `direction`. `PI` sends them every which way.
+    pub spread: f32,
+    /// Fraction of velocity kept after one second
+    pub drag: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub start_size: f32,
+    pub end_size: f32,
+    /// Plain squares if `None`
+    pub texture: Option<Handle<Image>>,
+}
+
+impl Default for ParticleEffect {
+    fn default() -> Self {
+        ParticleEffect {
+            lifetime: 0.5,
+            min_speed: 0.5,
+            max_speed: 1.0,
+            direction: Vec2::X,
+            spread: PI,
+            drag: 1.0,
+            start_color: Color::WHITE,
+            end_color: Color::WHITE.with_a(0.0),
+            start_size: 0.1,
+            end_size: 0.0,
+            texture: None,
+        }
+    }
+}
+
+/// Continuously spawns particles at this entity's position
+#[derive(Component, Debug, Clone, Default)]
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    /// Particles per second
+    pub rate: f32,
+    accumulator: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: ParticleEffect, rate: f32) -> Self {
+        ParticleEmitter {
+            effect,
+            rate,
+            accumulator: 0.0,
+        }
+    }
+}
+
+/// Spawns `count` particles at once at `position`
+#[derive(Event, Debug)]
+pub struct ParticleBurst {
+    pub effect: ParticleEffect,
+    pub position: Vec3,
+    pub count: u32,
+}
+
+/// Caps how many particles can be alive at once, anything over the budget simply isn't spawned
+#[derive(Resource, Debug)]
+pub struct ParticleBudget {
+    pub max_particles: usize,
+    live: usize,
+}
+
+impl Default for ParticleBudget {
+    fn default() -> Self {
+        ParticleBudget {
+            max_particles: 1500,
+            live: 0,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct Particle {
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    drag: f32,
+    start_color: Color,
+    end_color: Color,
+    start_size: f32,
+    end_size: f32,
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let [ar, ag, ab, aa] = a.as_rgba_f32();
+    let [br, bg, bb, ba] = b.as_rgba_f32();
+    Color::rgba(
+        ar + (br - ar) * t,
+        ag + (bg - ag) * t,
+        ab + (bb - ab) * t,
+        aa + (ba - aa) * t,
+    )
+}
+
+fn spawn_particle(commands: &mut Commands, effect: &ParticleEffect, position: Vec3) {
+    let mut rng = rand::thread_rng();
+    let angle = if effect.spread > 0.0 {
+        rng.gen_range(-effect.spread..=effect.spread)
+    } else {
+        0.0
+    };
+    let speed = if effect.max_speed > effect.min_speed {
+        rng.gen_range(effect.min_speed..effect.max_speed)
+    } else {
+        effect.min_speed
+    };
+    let velocity = Vec2::from_angle(angle).rotate(effect.direction.normalize_or_zero()) * speed;
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: effect.start_color,
+                custom_size: Some(Vec2::splat(effect.start_size)),
+                ..Default::default()
+            },
+            texture: effect.texture.clone().unwrap_or_default(),
+            transform: Transform::from_translation(position),
+            ..Default::default()
+        },
+        Particle {
+            velocity,
+            age: 0.0,
+            lifetime: effect.lifetime,
+            drag: effect.drag,
+            start_color: effect.start_color,
+            end_color: effect.end_color,
+            start_size: effect.start_size,
+            end_size: effect.end_size,
+        },
+    ));
+}
+
+fn emit_particles(
+    mut commands: Commands,
+    mut emitter_query: Query<(&mut ParticleEmitter, &GlobalTransform)>,
+    mut bursts: EventReader<ParticleBurst>,
+    mut budget: ResMut<ParticleBudget>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+
+    for ev in bursts.read() {
+        let count = (ev.count as usize).min(budget.max_particles.saturating_sub(budget.live));
+        for _ in 0..count {
+            spawn_particle(&mut commands, &ev.effect, ev.position);
+        }
+        budget.live += count;
+    }
+
+    for (mut emitter, transform) in emitter_query.iter_mut() {
+        emitter.accumulator += emitter.rate * dt;
+        let wanted = emitter.accumulator.floor();
+        emitter.accumulator -= wanted;
+
+        let count = (wanted as usize).min(budget.max_particles.saturating_sub(budget.live));
+        // Draw particles just behind whatever is emitting them
+        let position = transform.translation() - Vec3::Z * 0.01;
+        for _ in 0..count {
+            spawn_particle(&mut commands, &emitter.effect, position);
+        }
+        budget.live += count;
+    }
+}
+
+fn update_particles(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    mut budget: ResMut<ParticleBudget>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    let mut live = 0;
+    for (e, mut particle, mut transform, mut sprite) in query.iter_mut() {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(e).despawn_recursive();
+            continue;
+        }
+        live += 1;
+
+        transform.translation += (particle.velocity * dt).extend(0.0);
+        let drag = particle.drag.powf(dt);
+        particle.velocity *= drag;
+
+        let t = particle.age / particle.lifetime;
+        sprite.color = lerp_color(particle.start_color, particle.end_color, t);
+        sprite.custom_size = Some(Vec2::splat(
+            particle.start_size + (particle.end_size - particle.start_size) * t,
+        ));
+    }
+    budget.live = live;
+}
+
+fn cleanup_particles(mut commands: Commands, query: Query<Entity, With<Particle>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ParticleBurst>()
+            .init_resource::<ParticleBudget>()
+            .add_systems(
+                Update,
+                (emit_particles, update_particles)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), cleanup_particles);
+    }
+}
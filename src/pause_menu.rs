@@ -1,28 +1,36 @@
-use std::ops::RangeInclusive;
-
 use bevy::prelude::*;
 use bevy_egui::{egui::Layout, *};
 
 use crate::{
     audio::VolumeSettings,
+    hit_feedback::DamageNumberSettings,
+    input::{Action, ActionState},
+    localization::Localization,
     states::{AppState, GameState},
-    ui::square_button,
+    touch::MenuTap,
+    ui::{square_button, volume_controls},
 };
 
-fn formatter(x: f64, _decimal_places: RangeInclusive<usize>) -> String {
-    format!("{x:.02}")
-}
-
 fn pause_menu(
     mut egui_contexts: EguiContexts,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut next_app_state: ResMut<NextState<AppState>>,
     mut volume_settings: ResMut<VolumeSettings>,
+    mut damage_number_settings: ResMut<DamageNumberSettings>,
+    mut taps: EventReader<MenuTap>,
+    localization: Res<Localization>,
 ) {
+    if taps.read().count() > 0 {
+        next_game_state.set(GameState::Playing);
+        return;
+    }
+
     let ctx = egui_contexts.ctx_mut();
     volume_settings.bypass_change_detection();
+    damage_number_settings.bypass_change_detection();
 
-    egui::Window::new("Paused")
+    egui::Window::new(localization.get("pause.title"))
+        .id(egui::Id::new("paused"))
         .default_width(600.0)
         .resizable(false)
         .movable(false)
@@ -30,27 +38,38 @@ fn pause_menu(
         .collapsible(false)
         .show(ctx, |ui| {
             ui.with_layout(Layout::top_down_justified(egui::Align::Center), |ui| {
-                if ui.add(square_button("Resume")).clicked() {
+                if ui
+                    .add(square_button(localization.get("pause.resume")))
+                    .clicked()
+                {
                     next_game_state.set(GameState::Playing);
                 }
-                if ui.add(square_button("Restart")).clicked() {
+                if ui
+                    .add(square_button(localization.get("pause.restart")))
+                    .clicked()
+                {
                     next_app_state.set(AppState::Restart);
                     next_game_state.set(GameState::Playing);
                 }
-                if ui.add(square_button("Exit to Menu")).clicked() {
+                if ui
+                    .add(square_button(localization.get("pause.exit")))
+                    .clicked()
+                {
                     next_app_state.set(AppState::MainMenu);
                     next_game_state.set(GameState::Playing);
                 }
+                if volume_controls(ui, &mut volume_settings, &localization) {
+                    volume_settings.set_changed();
+                    debug!("Volume changed to {:?}", *volume_settings);
+                }
                 if ui
-                    .add(
-                        egui::Slider::new(&mut volume_settings.volume, 0.0..=2.0)
-                            .text("Sound")
-                            .custom_formatter(formatter),
+                    .checkbox(
+                        &mut damage_number_settings.enabled,
+                        localization.get("settings.damage_numbers"),
                     )
                     .changed()
                 {
-                    volume_settings.set_changed();
-                    debug!("Sound volume changed to {}", volume_settings.volume);
+                    damage_number_settings.set_changed();
                 }
             });
         });
@@ -59,9 +78,9 @@ fn pause_menu(
 fn toggle_pause_menu(
     current_game_state: Res<State<GameState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
-    input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
 ) {
-    if !input.just_pressed(KeyCode::Escape) {
+    if !actions.just_pressed(Action::Pause) {
         return;
     }
     match *current_game_state.get() {
@@ -1,18 +1,26 @@
-use std::f32::consts::PI;
-
-use bevy::{math::vec2, prelude::*, window::PrimaryWindow};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
 
 use crate::{
-    character, devices,
+    animation::{build_atlas, frame_index, AnimationClip, AnimationSet, Animator, Clip, Facing},
+    auto_aim::AutoAimTarget,
+    character, dash, devices,
+    difficulty::NightFinished,
     enemy::Enemy,
     experience::ExperienceCounter,
-    health::{DamageEvent, DeathEvent, Health},
+    health::{
+        Armor, DamageEvent, DeathEvent, HealEvent, Health, Invulnerability, Regeneration, Shield,
+    },
+    input::ActionState,
     loading::LoadingAssets,
     map::PlayerSpawner,
     physics,
     states::AppState,
+    status::{ApplyStatusEffect, StatusEffects},
 };
 
 #[derive(Debug, Component)]
@@ -32,6 +40,7 @@ pub struct PlayerBundle {
     pub character: character::Character,
     pub health: Health,
     pub experience_counter: ExperienceCounter,
+    pub status_effects: StatusEffects,
 
     pub rigid_body: RigidBody,
     pub velocity: Velocity,
@@ -47,43 +56,97 @@ pub struct PlayerBundle {
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 
-    pub sprite: Sprite,
-    pub texture: Handle<Image>,
+    pub sprite: TextureAtlasSprite,
+    pub texture_atlas: Handle<TextureAtlas>,
+    pub animator: Animator,
 }
 
-#[derive(Debug, Resource)]
+#[derive(Debug, Default, Resource)]
 struct PlayerAssets {
-    texture_right: Handle<Image>,
-    texture_left: Handle<Image>,
-    texture_up: Handle<Image>,
-    texture_down: Handle<Image>,
-    texture_dead: Handle<Image>,
+    /// Individual frames, packed into a single atlas by `build_player_animations` once they've loaded
+    frames: Vec<Handle<Image>>,
+    atlas: Handle<TextureAtlas>,
+    animations: Handle<AnimationSet>,
 }
 
+// Indices into `PlayerAssets::frames`
+const FRAME_RIGHT: usize = 0;
+const FRAME_UP: usize = 1;
+const FRAME_LEFT: usize = 2;
+const FRAME_DOWN: usize = 3;
+const FRAME_DEAD: usize = 4;
+const FRAME_HIT: usize = 5;
+
 fn load_player_assets(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut loading_assets: ResMut<LoadingAssets>,
 ) {
-    let texture_right = asset_server.load("textures/guy right.png");
-    let texture_left = asset_server.load("textures/guy left.png");
-    let texture_up = asset_server.load("textures/guy up.png");
-    let texture_down = asset_server.load("textures/guy down.png");
-    let texture_dead = asset_server.load("textures/guy dead.png");
-
-    loading_assets.add(texture_right.clone());
-    loading_assets.add(texture_left.clone());
-    loading_assets.add(texture_up.clone());
-    loading_assets.add(texture_down.clone());
-    loading_assets.add(texture_dead.clone());
+    let frames: Vec<Handle<Image>> = [
+        "textures/guy right.png",
+        "textures/guy up.png",
+        "textures/guy left.png",
+        "textures/guy down.png",
+        "textures/guy dead.png",
+        "textures/guy.png",
+    ]
+    .into_iter()
+    .map(|path| asset_server.load(path))
+    .collect();
+
+    for frame in frames.iter() {
+        loading_assets.add(frame.clone());
+    }
 
     commands.insert_resource(PlayerAssets {
-        texture_right,
-        texture_left,
-        texture_down,
-        texture_up,
-        texture_dead,
+        frames,
+        ..Default::default()
+    });
+}
+
+fn build_player_animations(
+    mut player_assets: ResMut<PlayerAssets>,
+    mut textures: ResMut<Assets<Image>>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+    mut animation_sets: ResMut<Assets<AnimationSet>>,
+) {
+    let Some(atlas) = build_atlas(&player_assets.frames, &mut textures) else {
+        return;
+    };
+    let frame = |i: usize| frame_index(&atlas, &player_assets.frames[i]);
+
+    let mut clips = HashMap::new();
+    for (facing, i) in [
+        (Facing::Right, FRAME_RIGHT),
+        (Facing::Up, FRAME_UP),
+        (Facing::Left, FRAME_LEFT),
+        (Facing::Down, FRAME_DOWN),
+    ] {
+        clips.insert(
+            Clip::Idle(facing),
+            AnimationClip::new(vec![frame(i)], 0.5, true),
+        );
+        // There's only one frame per direction for now, so walking looks just like standing still
+        clips.insert(
+            Clip::Walk(facing),
+            AnimationClip::new(vec![frame(i)], 0.15, true),
+        );
+    }
+    clips.insert(
+        Clip::Hit,
+        AnimationClip::new(vec![frame(FRAME_HIT)], 0.2, false),
+    );
+    clips.insert(
+        Clip::Death,
+        AnimationClip::new(vec![frame(FRAME_DEAD)], 1.0, false),
+    );
+
+    let atlas = atlases.add(atlas);
+    player_assets.animations = animation_sets.add(AnimationSet {
+        atlas: atlas.clone(),
+        clips,
     });
+    player_assets.atlas = atlas;
 }
 
 fn spawn_player(
@@ -106,7 +169,8 @@ fn spawn_player(
 
     commands
         .spawn(PlayerBundle {
-            texture: player_assets.texture_right.clone(),
+            texture_atlas: player_assets.atlas.clone(),
+            animator: Animator::new(player_assets.animations.clone()),
             collider: Collider::ball(0.5 / physics::PHYSICS_SCALE),
             collision_groups: CollisionGroups::new(
                 physics::PLAYER_GROUP,
@@ -127,34 +191,28 @@ fn spawn_player(
             transform: t,
             ..Default::default()
         })
-        .insert(devices::fireball::FireballLauncher::default());
+        .insert((
+            devices::fireball::FireballLauncher::default(),
+            dash::Dash::default(),
+            Regeneration { per_second: 0.1 },
+            Invulnerability::new(0.75),
+            Shield::new(5.0, 4.0, 1.0),
+            // No armor to start with, but it's there for upgrades and items to raise
+            Armor::default(),
+        ));
 }
 
 fn move_player(
     mut query: Query<(&Player, &mut character::Character, &Health)>,
-    input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
 ) {
-    let mut desired_direction = Vec2::ZERO;
-    if input.pressed(KeyCode::A) {
-        desired_direction += vec2(-1.0, 0.0);
-    }
-    if input.pressed(KeyCode::D) {
-        desired_direction += vec2(1.0, 0.0);
-    }
-    if input.pressed(KeyCode::W) {
-        desired_direction += vec2(0.0, 1.0);
-    }
-    if input.pressed(KeyCode::S) {
-        desired_direction += vec2(0.0, -1.0);
-    }
-    let desired_velocity = desired_direction.length().clamp(0.0, 1.0);
-    desired_direction = desired_direction.normalize_or_zero();
+    let desired_direction = actions.movement();
 
     for (_player, mut character, health) in query.iter_mut() {
         if health.dead {
             continue;
         }
-        character.desired_direction = desired_direction * desired_velocity;
+        character.desired_direction = desired_direction;
     }
 }
 
@@ -165,104 +223,136 @@ fn cleanup_player(mut commands: Commands, query: Query<Entity, With<Player>>) {
 }
 
 fn face_player(
-    mut query: Query<(&mut Player, &mut Handle<Image>, &Transform, &Health)>,
-    main_window_query: Query<&Window, With<PrimaryWindow>>,
-    main_camera_query: Query<(&Camera, &GlobalTransform)>,
-    player_assets: Res<PlayerAssets>,
-    mut last_facing: Local<u8>,
+    mut query: Query<(&mut Player, &mut Animator, &Transform, &Health)>,
+    actions: Res<ActionState>,
+    auto_aim: Res<AutoAimTarget>,
 ) {
-    let Ok(main_window) = main_window_query.get_single() else {
-        return;
-    };
-
-    let Ok((main_camera, camera_transform)) = main_camera_query.get_single() else {
-        error!("Didn't find exactly one camera! make a marker component or something");
-        return;
-    };
-
-    for (mut player, mut player_sprite, transform, health) in query.iter_mut() {
+    for (mut player, mut animator, transform, health) in query.iter_mut() {
         if health.dead {
-            *player_sprite = player_assets.texture_dead.clone();
             continue;
         }
         let player_pos = transform.translation.truncate();
 
-        let Some(cursor_pos) = main_window.cursor_position() else {
-            continue;
-        };
-        let Some(cursor_pos) = main_camera.viewport_to_world_2d(camera_transform, cursor_pos)
+        let Some(dir) = auto_aim
+            .direction(player_pos)
+            .or_else(|| actions.aim_direction(player_pos))
         else {
             continue;
         };
 
-        let dir = (cursor_pos - player_pos).normalize_or_zero();
-
         player.facing = dir;
-
-        let angle = Vec2::X.angle_between(dir);
-        let facing: u8;
-        if angle < 0.25 * PI && angle > -0.25 * PI {
-            // right
-            facing = 0;
-        } else if angle < 0.75 * PI && angle >= 0.25 * PI {
-            // up
-            facing = 1;
-        } else if angle <= -0.25 * PI && angle > -0.75 * PI {
-            // down
-            facing = 3;
-        } else {
-            // left
-            facing = 2;
-        }
-
-        if facing != *last_facing {
-            match facing {
-                0 => *player_sprite = player_assets.texture_right.clone(),
-                1 => *player_sprite = player_assets.texture_up.clone(),
-                2 => *player_sprite = player_assets.texture_left.clone(),
-                3 => *player_sprite = player_assets.texture_down.clone(),
-                _ => unreachable!(),
-            }
-
-            *last_facing = facing;
-        }
+        animator.facing = Facing::from_direction(dir);
     }
 }
 
 fn handle_player_collision(
-    mut player_query: Query<(Entity, &Transform, &mut Velocity), With<Player>>,
-    other_query: Query<(&Transform, Option<&Enemy>), Without<Player>>,
+    mut player_query: Query<
+        (Entity, &Transform, &mut Velocity, Option<&Invulnerability>),
+        With<Player>,
+    >,
+    mut enemy_query: Query<(&Transform, &mut Enemy, Option<&StatusEffects>), Without<Player>>,
     mut collision_events: EventReader<CollisionEvent>,
     mut damage_events: EventWriter<DamageEvent>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    time: Res<Time>,
+    mut touching: Local<HashSet<Entity>>,
 ) {
+    // Keep track of every enemy currently touching the player
     for ev in collision_events.read() {
-        let &CollisionEvent::Started(e1, e2, _) = ev else {
+        let (e1, e2, started) = match *ev {
+            CollisionEvent::Started(e1, e2, _) => (e1, e2, true),
+            CollisionEvent::Stopped(e1, e2, _) => (e1, e2, false),
+        };
+
+        let enemy_entity = if player_query.contains(e1) && enemy_query.contains(e2) {
+            e2
+        } else if player_query.contains(e2) && enemy_query.contains(e1) {
+            e1
+        } else {
             continue;
         };
 
-        let ((player_entity, player_transform, mut player_velocity), (other_transform, enemy)) = {
-            if let (Ok(player), Ok(other)) = (player_query.get_mut(e1), other_query.get(e2)) {
-                (player, other)
-            } else if let (Ok(player), Ok(other)) = (player_query.get_mut(e2), other_query.get(e1))
-            {
-                (player, other)
-            } else {
-                continue;
-            }
+        if started {
+            touching.insert(enemy_entity);
+        } else {
+            touching.remove(&enemy_entity);
+        }
+    }
+
+    let dt = time.delta_seconds();
+    for (_, mut enemy, _) in enemy_query.iter_mut() {
+        if enemy.contact_cooldown_remaining > 0.0 {
+            enemy.contact_cooldown_remaining -= dt;
+        }
+    }
+
+    let Ok((player_entity, player_transform, mut player_velocity, invulnerability)) =
+        player_query.get_single_mut()
+    else {
+        return;
+    };
+
+    if invulnerability.is_some_and(|i| i.is_active()) {
+        return;
+    }
+
+    touching.retain(|e| enemy_query.contains(*e));
+
+    for enemy_entity in touching.iter() {
+        let Ok((enemy_transform, mut enemy, status_effects)) = enemy_query.get_mut(*enemy_entity)
+        else {
+            continue;
         };
 
-        if let Some(enemy) = enemy {
-            damage_events.send(DamageEvent {
+        if enemy.contact_cooldown_remaining > 0.0 || status_effects.is_some_and(|s| s.is_stunned())
+        {
+            continue;
+        }
+        enemy.contact_cooldown_remaining = enemy.contact_cooldown;
+
+        damage_events.send(DamageEvent {
+            entity: player_entity,
+            amount: enemy.damage,
+            damage_type: enemy.damage_type,
+            crit: false,
+            over_time: false,
+        });
+        status_events.send_batch(enemy.contact_effects.iter().map(|&(effect, duration)| {
+            ApplyStatusEffect {
                 entity: player_entity,
-                amount: enemy.damage,
-            });
+                effect,
+                duration,
+            }
+        }));
+
+        // knockback
+        let knockback = (player_transform.translation.truncate()
+            - enemy_transform.translation.truncate())
+        .normalize_or_zero()
+            * enemy.knockback;
+        player_velocity.linvel += knockback;
+
+        // Only one enemy gets to hit the player per frame, the rest are blocked by invulnerability anyway
+        if invulnerability.is_some() {
+            break;
+        }
+    }
+}
+
+/// Fraction of the player's maximum health restored after surviving a night
+const NIGHT_HEAL_FRACTION: f32 = 0.5;
 
-            // knockback
-            let knockback = (player_transform.translation.truncate()
-                - other_transform.translation.truncate())
-            .normalize_or_zero()
-                * enemy.knockback;
-            player_velocity.linvel += knockback;
+fn heal_between_nights(
+    player_query: Query<(Entity, &Health), With<Player>>,
+    mut night_finished: EventReader<NightFinished>,
+    mut heal_events: EventWriter<HealEvent>,
+) {
+    for _ in night_finished.read() {
+        for (entity, health) in player_query.iter() {
+            heal_events.send(HealEvent {
+                entity,
+                amount: health.maximum * NIGHT_HEAL_FRACTION,
+            });
         }
     }
 }
@@ -292,6 +382,7 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, load_player_assets)
+            .add_systems(OnExit(AppState::Loading), build_player_animations)
             .add_systems(
                 Update,
                 (
@@ -300,9 +391,100 @@ impl Plugin for PlayerPlugin {
                     handle_player_collision,
                     spawn_player,
                     handle_player_death,
+                    heal_between_nights,
                 )
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(OnExit(AppState::InGame), cleanup_player);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{ecs::event::ManualEventReader, time::TimeUpdateStrategy};
+
+    use super::*;
+
+    /// Each update moves time forward by this much
+    const FRAME: Duration = Duration::from_millis(100);
+
+    /// An app with a player and an enemy that has just started touching them
+    fn test_app() -> (App, Entity, Entity) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .add_event::<CollisionEvent>()
+            .add_event::<DamageEvent>()
+            .add_event::<ApplyStatusEffect>()
+            .add_systems(Update, handle_player_collision);
+
+        let player = app
+            .world
+            .spawn((Player::default(), Transform::default(), Velocity::default()))
+            .id();
+        let enemy = app
+            .world
+            .spawn((
+                Enemy {
+                    damage: 1.0,
+                    contact_cooldown: 1.0,
+                    ..Default::default()
+                },
+                Transform::from_xyz(1.0, 0.0, 0.0),
+            ))
+            .id();
+        app.world.send_event(CollisionEvent::Started(
+            player,
+            enemy,
+            CollisionEventFlags::empty(),
+        ));
+
+        (app, player, enemy)
+    }
+
+    /// Runs `frames` updates and returns how many hits landed on the player in that time
+    fn hits(app: &mut App, reader: &mut ManualEventReader<DamageEvent>, frames: usize) -> usize {
+        let mut hits = 0;
+        for _ in 0..frames {
+            app.update();
+            hits += reader
+                .read(app.world.resource::<Events<DamageEvent>>())
+                .count();
+        }
+        hits
+    }
+
+    #[test]
+    fn contact_cooldown_stops_repeated_hits() {
+        let (mut app, _, _) = test_app();
+        let mut reader = ManualEventReader::default();
+
+        assert_eq!(hits(&mut app, &mut reader, 1), 1);
+        assert_eq!(hits(&mut app, &mut reader, 5), 0);
+    }
+
+    #[test]
+    fn continued_contact_hits_again_after_the_cooldown() {
+        let (mut app, _, _) = test_app();
+        let mut reader = ManualEventReader::default();
+
+        assert_eq!(hits(&mut app, &mut reader, 1), 1);
+        assert_eq!(hits(&mut app, &mut reader, 12), 1);
+    }
+
+    #[test]
+    fn contact_ending_stops_hits() {
+        let (mut app, player, enemy) = test_app();
+        let mut reader = ManualEventReader::default();
+
+        assert_eq!(hits(&mut app, &mut reader, 1), 1);
+        app.world.send_event(CollisionEvent::Stopped(
+            player,
+            enemy,
+            CollisionEventFlags::empty(),
+        ));
+        assert_eq!(hits(&mut app, &mut reader, 15), 0);
+    }
+}
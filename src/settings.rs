@@ -0,0 +1,472 @@
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use bevy_egui::{egui::Layout, *};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    accessibility::{AccessibilitySettings, ColorPalette},
+    audio::VolumeSettings,
+    auto_aim::{AutoAimMode, AutoAimSettings},
+    camera::CameraSettings,
+    debug::DebugOverlay,
+    hit_feedback::DamageNumberSettings,
+    input::{controls_ui, InputBindings, Rebinding},
+    localization::{Language, LanguageSettings, Localization},
+    states::AppState,
+    storage,
+    ui::{square_button, volume_controls},
+};
+
+/// Key settings are saved under, see `storage.rs`
+const SETTINGS_KEY: &str = "settings";
+
+/// Window resolutions offered in the settings screen
+const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1366, 768),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+];
+
+/// UI scales offered in the settings screen
+const UI_SCALES: [f32; 5] = [0.75, 1.0, 1.25, 1.5, 2.0];
+
+/// The browser decides how big the canvas is, so there's no point offering window options there
+const WINDOW_OPTIONS: bool = !cfg!(target_arch = "wasm32");
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    fn name<'a>(&self, localization: &'a Localization) -> &'a str {
+        localization.get(match self {
+            DisplayMode::Windowed => "settings.windowed",
+            DisplayMode::Borderless => "settings.borderless",
+            DisplayMode::Fullscreen => "settings.fullscreen",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoSettings {
+    pub display_mode: DisplayMode,
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        VideoSettings {
+            display_mode: DisplayMode::Windowed,
+            resolution: (1280, 720),
+            vsync: true,
+        }
+    }
+}
+
+/// Everything that gets saved between sessions.
+///
+/// Each part lives in its own resource while the game is running, this just gathers them up into
+/// one file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedSettings {
+    audio: VolumeSettings,
+    video: VideoSettings,
+    bindings: InputBindings,
+    damage_numbers: bool,
+    screen_shake: f32,
+    debug_overlay: bool,
+    auto_aim: AutoAimSettings,
+    accessibility: AccessibilitySettings,
+    language: Language,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        SavedSettings {
+            audio: VolumeSettings::default(),
+            video: VideoSettings::default(),
+            bindings: InputBindings::default(),
+            damage_numbers: DamageNumberSettings::default().enabled,
+            screen_shake: CameraSettings::default().screen_shake,
+            debug_overlay: false,
+            auto_aim: AutoAimSettings::default(),
+            accessibility: AccessibilitySettings::default(),
+            language: Language::default(),
+        }
+    }
+}
+
+fn save_settings(
+    volume_settings: Res<VolumeSettings>,
+    video_settings: Res<VideoSettings>,
+    bindings: Res<InputBindings>,
+    damage_number_settings: Res<DamageNumberSettings>,
+    camera_settings: Res<CameraSettings>,
+    debug_overlay: Res<DebugOverlay>,
+    auto_aim_settings: Res<AutoAimSettings>,
+    accessibility: Res<AccessibilitySettings>,
+    language_settings: Res<LanguageSettings>,
+    mut dirty: Local<bool>,
+    mut since_change: Local<f32>,
+    time: Res<Time<Real>>,
+) {
+    let changed = [
+        volume_settings.is_changed() && !volume_settings.is_added(),
+        video_settings.is_changed() && !video_settings.is_added(),
+        bindings.is_changed() && !bindings.is_added(),
+        damage_number_settings.is_changed() && !damage_number_settings.is_added(),
+        camera_settings.is_changed() && !camera_settings.is_added(),
+        debug_overlay.is_changed() && !debug_overlay.is_added(),
+        auto_aim_settings.is_changed() && !auto_aim_settings.is_added(),
+        accessibility.is_changed() && !accessibility.is_added(),
+        language_settings.is_changed() && !language_settings.is_added(),
+    ];
+    if changed.contains(&true) {
+        *dirty = true;
+        *since_change = 0.0;
+    }
+    *since_change += time.delta_seconds();
+
+    // Sliders change every frame while they're being dragged, so wait for them to settle
+    if !*dirty || *since_change < 0.5 {
+        return;
+    }
+    *dirty = false;
+
+    storage::save(
+        SETTINGS_KEY,
+        &SavedSettings {
+            audio: volume_settings.clone(),
+            video: video_settings.clone(),
+            bindings: bindings.clone(),
+            damage_numbers: damage_number_settings.enabled,
+            screen_shake: camera_settings.screen_shake,
+            debug_overlay: debug_overlay.enabled,
+            auto_aim: auto_aim_settings.clone(),
+            accessibility: accessibility.clone(),
+            language: language_settings.language,
+        },
+    );
+}
+
+fn apply_video_settings(
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+    video_settings: Res<VideoSettings>,
+) {
+    if !video_settings.is_changed() {
+        return;
+    }
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+
+    window.present_mode = if video_settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+
+    if WINDOW_OPTIONS {
+        window.mode = match video_settings.display_mode {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        };
+        let (width, height) = video_settings.resolution;
+        window.resolution.set(width as f32, height as f32);
+    }
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct SettingsScreen {
+    pub open: bool,
+}
+
+fn settings_screen(
+    mut egui_contexts: EguiContexts,
+    mut settings_screen: ResMut<SettingsScreen>,
+    mut volume_settings: ResMut<VolumeSettings>,
+    mut video_settings: ResMut<VideoSettings>,
+    mut bindings: ResMut<InputBindings>,
+    mut rebinding: ResMut<Rebinding>,
+    mut damage_number_settings: ResMut<DamageNumberSettings>,
+    mut camera_settings: ResMut<CameraSettings>,
+    mut debug_overlay: ResMut<DebugOverlay>,
+    mut auto_aim_settings: ResMut<AutoAimSettings>,
+    mut accessibility: ResMut<AccessibilitySettings>,
+    mut language_settings: ResMut<LanguageSettings>,
+    localization: Res<Localization>,
+) {
+    let ctx = egui_contexts.ctx_mut();
+
+    if !settings_screen.open {
+        egui::Area::new("settings button")
+            .anchor(egui::Align2::RIGHT_TOP, egui::Vec2::new(-10.0, 10.0))
+            .show(ctx, |ui| {
+                if ui
+                    .add(square_button(localization.get("settings.open")))
+                    .clicked()
+                {
+                    settings_screen.open = true;
+                }
+            });
+        return;
+    }
+
+    // Only mark things as changed when they actually are, so they don't get saved every frame
+    volume_settings.bypass_change_detection();
+    video_settings.bypass_change_detection();
+    bindings.bypass_change_detection();
+    damage_number_settings.bypass_change_detection();
+    camera_settings.bypass_change_detection();
+    debug_overlay.bypass_change_detection();
+    auto_aim_settings.bypass_change_detection();
+    accessibility.bypass_change_detection();
+    language_settings.bypass_change_detection();
+
+    egui::Window::new(localization.get("settings.title"))
+        .id(egui::Id::new("settings"))
+        .default_width(800.0)
+        .resizable(false)
+        .movable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .collapsible(false)
+        // The controls list makes this too tall for small windows
+        .vscroll(true)
+        .show(ctx, |ui| {
+            egui::ComboBox::from_label(localization.get("settings.language"))
+                .selected_text(language_settings.language.native_name())
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        if ui
+                            .selectable_value(
+                                &mut language_settings.language,
+                                language,
+                                language.native_name(),
+                            )
+                            .changed()
+                        {
+                            language_settings.set_changed();
+                        }
+                    }
+                });
+
+            ui.separator();
+            ui.heading(localization.get("settings.audio"));
+            if volume_controls(ui, &mut volume_settings, &localization) {
+                volume_settings.set_changed();
+            }
+
+            ui.separator();
+            ui.heading(localization.get("settings.video"));
+            let mut video_changed = false;
+            if WINDOW_OPTIONS {
+                egui::ComboBox::from_label(localization.get("settings.display_mode"))
+                    .selected_text(video_settings.display_mode.name(&localization))
+                    .show_ui(ui, |ui| {
+                        for mode in [
+                            DisplayMode::Windowed,
+                            DisplayMode::Borderless,
+                            DisplayMode::Fullscreen,
+                        ] {
+                            video_changed |= ui
+                                .selectable_value(
+                                    &mut video_settings.display_mode,
+                                    mode,
+                                    mode.name(&localization),
+                                )
+                                .changed();
+                        }
+                    });
+                let (width, height) = video_settings.resolution;
+                egui::ComboBox::from_label(localization.get("settings.resolution"))
+                    .selected_text(format!("{width}x{height}"))
+                    .show_ui(ui, |ui| {
+                        for (width, height) in RESOLUTIONS {
+                            video_changed |= ui
+                                .selectable_value(
+                                    &mut video_settings.resolution,
+                                    (width, height),
+                                    format!("{width}x{height}"),
+                                )
+                                .changed();
+                        }
+                    });
+            }
+            video_changed |= ui
+                .checkbox(
+                    &mut video_settings.vsync,
+                    localization.get("settings.vsync"),
+                )
+                .changed();
+            if video_changed {
+                video_settings.set_changed();
+            }
+
+            ui.separator();
+            ui.heading(localization.get("settings.controls"));
+            if controls_ui(ui, &mut bindings, &mut rebinding, &localization) {
+                bindings.set_changed();
+            }
+
+            ui.separator();
+            ui.heading(localization.get("settings.accessibility"));
+            let mut accessibility_changed = false;
+            egui::ComboBox::from_label(localization.get("settings.color_palette"))
+                .selected_text(accessibility.palette.name(&localization))
+                .show_ui(ui, |ui| {
+                    for palette in ColorPalette::ALL {
+                        accessibility_changed |= ui
+                            .selectable_value(
+                                &mut accessibility.palette,
+                                palette,
+                                palette.name(&localization),
+                            )
+                            .changed();
+                    }
+                });
+            // Picked from a list rather than a slider, so the menu doesn't resize while dragging
+            egui::ComboBox::from_label(localization.get("settings.ui_scale"))
+                .selected_text(format!("{:.0}%", accessibility.ui_scale * 100.0))
+                .show_ui(ui, |ui| {
+                    for scale in UI_SCALES {
+                        accessibility_changed |= ui
+                            .selectable_value(
+                                &mut accessibility.ui_scale,
+                                scale,
+                                format!("{:.0}%", scale * 100.0),
+                            )
+                            .changed();
+                    }
+                });
+            accessibility_changed |= ui
+                .checkbox(
+                    &mut accessibility.reduced_motion,
+                    localization.get("settings.reduced_motion"),
+                )
+                .changed();
+            accessibility_changed |= ui
+                .checkbox(
+                    &mut accessibility.enemy_outlines,
+                    localization.get("settings.enemy_outlines"),
+                )
+                .changed();
+            if accessibility_changed {
+                accessibility.set_changed();
+            }
+
+            ui.separator();
+            ui.heading(localization.get("settings.gameplay"));
+            if ui
+                .checkbox(
+                    &mut damage_number_settings.enabled,
+                    localization.get("settings.damage_numbers"),
+                )
+                .changed()
+            {
+                damage_number_settings.set_changed();
+            }
+            if ui
+                .add_enabled(
+                    !accessibility.reduced_motion,
+                    egui::Slider::new(&mut camera_settings.screen_shake, 0.0..=1.0)
+                        .text(localization.get("settings.screen_shake")),
+                )
+                .changed()
+            {
+                camera_settings.set_changed();
+            }
+            let mut auto_aim_changed = false;
+            egui::ComboBox::from_label(localization.get("settings.auto_aim"))
+                .selected_text(auto_aim_settings.mode.name(&localization))
+                .show_ui(ui, |ui| {
+                    for mode in AutoAimMode::ALL {
+                        auto_aim_changed |= ui
+                            .selectable_value(
+                                &mut auto_aim_settings.mode,
+                                mode,
+                                mode.name(&localization),
+                            )
+                            .changed();
+                    }
+                });
+            auto_aim_changed |= ui
+                .checkbox(
+                    &mut auto_aim_settings.auto_fire,
+                    localization.get("settings.auto_fire"),
+                )
+                .changed();
+            if auto_aim_changed {
+                auto_aim_settings.set_changed();
+            }
+            if ui
+                .checkbox(
+                    &mut debug_overlay.enabled,
+                    localization.get("settings.debug_overlay"),
+                )
+                .changed()
+            {
+                debug_overlay.set_changed();
+            }
+
+            ui.separator();
+            ui.with_layout(Layout::top_down_justified(egui::Align::Center), |ui| {
+                if ui
+                    .add(square_button(localization.get("settings.back")))
+                    .clicked()
+                {
+                    settings_screen.open = false;
+                    *rebinding = Rebinding::default();
+                }
+            });
+        });
+}
+
+fn close_settings_screen(
+    mut settings_screen: ResMut<SettingsScreen>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    settings_screen.open = false;
+    *rebinding = Rebinding::default();
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let saved = storage::load::<SavedSettings>(SETTINGS_KEY);
+
+        app.insert_resource(saved.audio)
+            .insert_resource(saved.video)
+            .insert_resource(saved.bindings)
+            .insert_resource(DamageNumberSettings {
+                enabled: saved.damage_numbers,
+            })
+            .insert_resource(CameraSettings {
+                screen_shake: saved.screen_shake,
+            })
+            .insert_resource(DebugOverlay {
+                enabled: saved.debug_overlay,
+            })
+            .insert_resource(saved.auto_aim)
+            .insert_resource(saved.accessibility)
+            .insert_resource(LanguageSettings {
+                language: saved.language,
+            })
+            .init_resource::<SettingsScreen>()
+            .add_systems(Update, (apply_video_settings, save_settings))
+            .add_systems(Update, settings_screen.run_if(in_state(AppState::MainMenu)))
+            .add_systems(OnExit(AppState::MainMenu), close_settings_screen);
+    }
+}
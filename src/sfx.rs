@@ -0,0 +1,394 @@
+use bevy::{
+    audio::{PlaybackMode, SpatialListener, Volume},
+    prelude::*,
+    utils::HashMap,
+};
+use rand::Rng;
+
+use crate::{
+    audio::{AudioChannel, VolumeSettings},
+    enemy::Enemy,
+    experience::{CollectExperience, LevelUp},
+    health::DamageTaken,
+    loading::LoadingAssets,
+    player::Player,
+};
+
+/// Distance between the listener's ears, see `place_emitter`
+pub const EAR_GAP: f32 = 1.0;
+/// Sounds this far to the side of the listener or further are panned all the way
+const FULL_PAN_DISTANCE: f32 = 6.0;
+
+/// Panning uses rodio's spatial sinks, which are skipped on wasm to keep audio cheap there.
+/// Sounds still get quieter with distance.
+const USE_PANNING: bool = !cfg!(target_arch = "wasm32");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    FireballLaunch,
+    EnemyHit,
+    EnemyDeath,
+    ExperienceCollect,
+    LevelUp,
+    PlayerDamage,
+}
+
+/// How a positional sound gets quieter as it gets further from the listener
+#[derive(Debug, Clone, Copy)]
+pub struct Attenuation {
+    /// Full volume up to this distance
+    pub min_distance: f32,
+    /// Silent from this distance on
+    pub max_distance: f32,
+    /// 1.0 fades linearly, higher values drop off faster close to the listener
+    pub rolloff: f32,
+}
+
+impl Default for Attenuation {
+    fn default() -> Self {
+        Attenuation {
+            min_distance: 3.0,
+            max_distance: 14.0,
+            rolloff: 2.0,
+        }
+    }
+}
+
+impl Attenuation {
+    pub fn volume_at(&self, distance: f32) -> f32 {
+        let range = self.max_distance - self.min_distance;
+        if range <= 0.0 {
+            return if distance <= self.min_distance {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        let t = ((distance - self.min_distance) / range).clamp(0.0, 1.0);
+        (1.0 - t).powf(self.rolloff)
+    }
+}
+
+/// How a single sound effect gets played
+#[derive(Debug, Clone)]
+struct SfxSettings {
+    source: Handle<AudioSource>,
+    volume: f32,
+    /// Playback speed, which also changes the pitch
+    speed: f32,
+    /// Maximum random change in speed either way, so repeated sounds don't get grating
+    pitch_variation: f32,
+    /// Maximum number of copies of this sound playing at once, extra requests are dropped
+    max_concurrent: usize,
+    /// Only used for sounds played at a position
+    attenuation: Attenuation,
+    channel: AudioChannel,
+}
+
+#[derive(Debug, Default, Resource)]
+struct SfxAssets {
+    sounds: HashMap<Sfx, SfxSettings>,
+}
+
+fn load_sfx_assets(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+) {
+    let launch = asset_server.load::<AudioSource>("sounds/fireball launch.mp3");
+    let hit = asset_server.load::<AudioSource>("sounds/fireball hit.mp3");
+    let fireworks = asset_server
+        .load::<AudioSource>("sounds/675780__craigsmith__s34-23-fireworks-launching-swooshes.wav");
+
+    loading_assets.add(launch.clone());
+    loading_assets.add(hit.clone());
+    loading_assets.add(fireworks.clone());
+
+    let sounds = HashMap::from([
+        (
+            Sfx::FireballLaunch,
+            SfxSettings {
+                source: launch.clone(),
+                volume: 0.5,
+                speed: 1.0,
+                pitch_variation: 0.1,
+                max_concurrent: 3,
+                attenuation: Attenuation::default(),
+                channel: AudioChannel::Sfx,
+            },
+        ),
+        (
+            Sfx::EnemyHit,
+            SfxSettings {
+                source: hit.clone(),
+                volume: 0.6,
+                speed: 1.0,
+                pitch_variation: 0.15,
+                max_concurrent: 6,
+                attenuation: Attenuation::default(),
+                channel: AudioChannel::Sfx,
+            },
+        ),
+        (
+            Sfx::EnemyDeath,
+            SfxSettings {
+                source: hit.clone(),
+                volume: 1.0,
+                speed: 0.6,
+                pitch_variation: 0.05,
+                max_concurrent: 4,
+                // Big ghosts dying should still be heard from across the map
+                attenuation: Attenuation {
+                    max_distance: 20.0,
+                    ..Default::default()
+                },
+                channel: AudioChannel::Sfx,
+            },
+        ),
+        (
+            Sfx::ExperienceCollect,
+            SfxSettings {
+                source: launch,
+                volume: 0.3,
+                speed: 2.5,
+                pitch_variation: 0.3,
+                max_concurrent: 4,
+                attenuation: Attenuation::default(),
+                channel: AudioChannel::Sfx,
+            },
+        ),
+        (
+            Sfx::LevelUp,
+            SfxSettings {
+                source: fireworks,
+                volume: 0.6,
+                speed: 1.0,
+                pitch_variation: 0.0,
+                max_concurrent: 1,
+                attenuation: Attenuation::default(),
+                channel: AudioChannel::Ui,
+            },
+        ),
+        (
+            Sfx::PlayerDamage,
+            SfxSettings {
+                source: hit,
+                volume: 1.0,
+                speed: 0.8,
+                pitch_variation: 0.05,
+                max_concurrent: 2,
+                attenuation: Attenuation::default(),
+                channel: AudioChannel::Sfx,
+            },
+        ),
+    ]);
+
+    commands.insert_resource(SfxAssets { sounds });
+}
+
+#[derive(Event, Debug)]
+pub struct PlaySfx {
+    pub sfx: Sfx,
+    /// Multiplies the sound's usual playback speed
+    pub speed: f32,
+    /// Where in the world the sound comes from. `None` plays it at full volume without panning.
+    pub position: Option<Vec2>,
+}
+
+impl PlaySfx {
+    pub fn new(sfx: Sfx) -> Self {
+        PlaySfx {
+            sfx,
+            speed: 1.0,
+            position: None,
+        }
+    }
+
+    pub fn at(sfx: Sfx, position: Vec2) -> Self {
+        PlaySfx {
+            position: Some(position),
+            ..PlaySfx::new(sfx)
+        }
+    }
+}
+
+/// A sound effect that's currently playing
+#[derive(Component, Debug)]
+struct SfxInstance {
+    sfx: Sfx,
+    volume: f32,
+    position: Option<Vec2>,
+    attenuation: Attenuation,
+    channel: AudioChannel,
+}
+
+impl SfxInstance {
+    /// Volume this sound should play at, before the SFX volume setting is applied
+    fn volume(&self, listener: Option<Vec3>) -> f32 {
+        match (self.position, listener) {
+            (Some(position), Some(listener)) => {
+                self.volume
+                    * self
+                        .attenuation
+                        .volume_at(position.distance(listener.truncate()))
+            }
+            _ => self.volume,
+        }
+    }
+}
+
+/// Where to put a spatial sound's emitter so it's panned towards `position`.
+///
+/// The emitter always stays between the listener's ears, which keeps rodio's own distance falloff
+/// out of the picture so `Attenuation` is the only thing deciding how loud it is.
+fn place_emitter(position: Vec2, listener: Vec3) -> Vec3 {
+    let pan = ((position.x - listener.x) / FULL_PAN_DISTANCE).clamp(-1.0, 1.0);
+    listener + Vec3::X * pan * EAR_GAP / 2.0
+}
+
+fn play_sfx(
+    mut commands: Commands,
+    query: Query<&SfxInstance>,
+    listener_query: Query<&GlobalTransform, With<SpatialListener>>,
+    mut reader: EventReader<PlaySfx>,
+    sfx_assets: Res<SfxAssets>,
+    volume_settings: Res<VolumeSettings>,
+) {
+    let listener = listener_query.get_single().ok().map(|t| t.translation());
+
+    let mut playing = HashMap::<Sfx, usize>::new();
+    for instance in query.iter() {
+        *playing.entry(instance.sfx).or_default() += 1;
+    }
+
+    for ev in reader.read() {
+        let Some(settings) = sfx_assets.sounds.get(&ev.sfx) else {
+            continue;
+        };
+
+        let instance = SfxInstance {
+            sfx: ev.sfx,
+            volume: settings.volume,
+            position: ev.position,
+            attenuation: settings.attenuation,
+            channel: settings.channel,
+        };
+        // Start at the right volume, `update_sfx` only gets to it on the next frame
+        let volume = volume_settings.volume(instance.channel) * instance.volume(listener);
+        // Too far away to hear or muted, don't waste one of the slots on it
+        if volume <= 0.0 {
+            continue;
+        }
+
+        let count = playing.entry(ev.sfx).or_default();
+        if *count >= settings.max_concurrent {
+            continue;
+        }
+        *count += 1;
+
+        let variation = if settings.pitch_variation > 0.0 {
+            rand::thread_rng().gen_range(-settings.pitch_variation..=settings.pitch_variation)
+        } else {
+            0.0
+        };
+
+        let emitter = match (ev.position, listener) {
+            (Some(position), Some(listener)) if USE_PANNING => {
+                Some(place_emitter(position, listener))
+            }
+            _ => None,
+        };
+
+        let mut sound = commands.spawn((
+            AudioBundle {
+                source: settings.source.clone(),
+                settings: PlaybackSettings {
+                    mode: PlaybackMode::Despawn,
+                    volume: Volume::new_relative(volume),
+                    speed: settings.speed * ev.speed * (1.0 + variation),
+                    spatial: emitter.is_some(),
+                    ..Default::default()
+                },
+            },
+            instance,
+        ));
+        if let Some(emitter) = emitter {
+            sound.insert(TransformBundle::from_transform(
+                Transform::from_translation(emitter),
+            ));
+        }
+    }
+}
+
+fn update_sfx(
+    mut query: Query<(
+        &SfxInstance,
+        Option<&AudioSink>,
+        Option<&SpatialAudioSink>,
+        Option<&mut Transform>,
+    )>,
+    listener_query: Query<&GlobalTransform, With<SpatialListener>>,
+    volume_settings: Res<VolumeSettings>,
+) {
+    let listener = listener_query.get_single().ok().map(|t| t.translation());
+
+    for (instance, sink, spatial_sink, transform) in query.iter_mut() {
+        let volume = volume_settings.volume(instance.channel) * instance.volume(listener);
+        if let Some(sink) = sink {
+            sink.set_volume(volume);
+        }
+        if let Some(sink) = spatial_sink {
+            sink.set_volume(volume);
+        }
+
+        // Keep panning right as the listener moves around
+        if let (Some(mut transform), Some(position), Some(listener)) =
+            (transform, instance.position, listener)
+        {
+            transform.translation = place_emitter(position, listener);
+        }
+    }
+}
+
+fn damage_sfx(
+    query: Query<(&GlobalTransform, Option<&Player>, Option<&Enemy>)>,
+    mut reader: EventReader<DamageTaken>,
+    mut writer: EventWriter<PlaySfx>,
+) {
+    for ev in reader.read() {
+        match query.get(ev.entity) {
+            Ok((_, Some(_), _)) => writer.send(PlaySfx::new(Sfx::PlayerDamage)),
+            Ok((transform, _, Some(_))) => writer.send(PlaySfx::at(
+                Sfx::EnemyHit,
+                transform.translation().truncate(),
+            )),
+            _ => {}
+        }
+    }
+}
+
+fn experience_sfx(
+    mut collect_reader: EventReader<CollectExperience>,
+    mut level_up_reader: EventReader<LevelUp>,
+    mut writer: EventWriter<PlaySfx>,
+) {
+    for _ in collect_reader.read() {
+        writer.send(PlaySfx::new(Sfx::ExperienceCollect));
+    }
+    for _ in level_up_reader.read() {
+        writer.send(PlaySfx::new(Sfx::LevelUp));
+    }
+}
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySfx>()
+            .add_systems(Startup, load_sfx_assets)
+            .add_systems(
+                Update,
+                ((damage_sfx, experience_sfx, play_sfx).chain(), update_sfx),
+            );
+    }
+}
@@ -0,0 +1,231 @@
+use std::mem::discriminant;
+
+use bevy::prelude::*;
+
+use crate::{
+    character::Character,
+    health::{DamageEvent, DamageType, DeathEvent, Health},
+    states::AppState,
+};
+
+/// Time between damage ticks for damage-over-time effects
+const DAMAGE_TICK: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusEffect {
+    /// Deals fire damage over time
+    Burn { damage_per_second: f32 },
+    /// Multiplies movement speed by `factor`
+    Slow { factor: f32 },
+    /// Can't move, but can still attack
+    Freeze,
+    /// Can't move or attack
+    Stun,
+}
+
+impl StatusEffect {
+    fn tint(&self) -> Color {
+        match self {
+            StatusEffect::Burn { .. } => Color::rgb(1.0, 0.6, 0.4),
+            StatusEffect::Slow { .. } => Color::rgb(0.7, 0.8, 1.0),
+            StatusEffect::Freeze => Color::rgb(0.5, 0.9, 1.0),
+            StatusEffect::Stun => Color::rgb(1.0, 1.0, 0.5),
+        }
+    }
+
+    /// Combines two effects of the same kind, keeping the stronger of the two
+    fn strongest(self, other: StatusEffect) -> StatusEffect {
+        match (self, other) {
+            (
+                StatusEffect::Burn {
+                    damage_per_second: a,
+                },
+                StatusEffect::Burn {
+                    damage_per_second: b,
+                },
+            ) => StatusEffect::Burn {
+                damage_per_second: f32::max(a, b),
+            },
+            (StatusEffect::Slow { factor: a }, StatusEffect::Slow { factor: b }) => {
+                StatusEffect::Slow {
+                    factor: f32::min(a, b),
+                }
+            }
+            _ => other,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ActiveEffect {
+    effect: StatusEffect,
+    remaining: f32,
+    until_next_tick: f32,
+}
+
+/// Status effects currently affecting an entity
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    active: Vec<ActiveEffect>,
+}
+
+impl StatusEffects {
+    /// Adds an effect, refreshing its duration if an effect of the same kind is already active
+    pub fn apply(&mut self, effect: StatusEffect, duration: f32) {
+        if let Some(existing) = self
+            .active
+            .iter_mut()
+            .find(|a| discriminant(&a.effect) == discriminant(&effect))
+        {
+            existing.effect = existing.effect.strongest(effect);
+            existing.remaining = f32::max(existing.remaining, duration);
+            return;
+        }
+
+        self.active.push(ActiveEffect {
+            effect,
+            remaining: duration,
+            until_next_tick: DAMAGE_TICK,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Returns `true` if the entity shouldn't be able to attack
+    pub fn is_stunned(&self) -> bool {
+        self.active
+            .iter()
+            .any(|a| matches!(a.effect, StatusEffect::Stun))
+    }
+
+    /// Returns the combined movement speed multiplier of every active effect
+    pub fn speed_multiplier(&self) -> f32 {
+        self.active
+            .iter()
+            .map(|a| match a.effect {
+                StatusEffect::Slow { factor } => factor,
+                StatusEffect::Freeze | StatusEffect::Stun => 0.0,
+                StatusEffect::Burn { .. } => 1.0,
+            })
+            .product()
+    }
+
+    /// Returns the color sprites should be tinted, mixing the colors of all active effects
+    pub fn tint(&self) -> Color {
+        self.active.iter().fold(Color::WHITE, |color, a| {
+            let tint = a.effect.tint();
+            Color::rgb(
+                color.r() * tint.r(),
+                color.g() * tint.g(),
+                color.b() * tint.b(),
+            )
+        })
+    }
+}
+
+#[derive(Event, Debug)]
+pub struct ApplyStatusEffect {
+    pub entity: Entity,
+    pub effect: StatusEffect,
+    pub duration: f32,
+}
+
+fn handle_apply_status_effects(
+    mut query: Query<(&mut StatusEffects, &Health)>,
+    mut reader: EventReader<ApplyStatusEffect>,
+) {
+    for ev in reader.read() {
+        let Ok((mut effects, health)) = query.get_mut(ev.entity) else {
+            continue;
+        };
+        if health.dead {
+            continue;
+        }
+        effects.apply(ev.effect, ev.duration);
+    }
+}
+
+fn tick_status_effects(
+    mut query: Query<(Entity, &mut StatusEffects, &Health, Option<&mut Character>)>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut effects, health, character) in query.iter_mut() {
+        if health.dead || effects.is_empty() {
+            continue;
+        }
+
+        for active in effects.active.iter_mut() {
+            active.remaining -= dt;
+            active.until_next_tick -= dt;
+
+            if active.until_next_tick <= 0.0 {
+                active.until_next_tick += DAMAGE_TICK;
+                if let StatusEffect::Burn { damage_per_second } = active.effect {
+                    damage_events.send(DamageEvent {
+                        entity,
+                        amount: damage_per_second * DAMAGE_TICK,
+                        damage_type: DamageType::Fire,
+                        crit: false,
+                        over_time: true,
+                    });
+                }
+            }
+        }
+        effects.active.retain(|a| a.remaining > 0.0);
+
+        if let Some(mut character) = character {
+            character.speed_multiplier = effects.speed_multiplier();
+        }
+    }
+}
+
+pub fn tint_status_effects(
+    mut query: Query<(&StatusEffects, &mut TextureAtlasSprite), Changed<StatusEffects>>,
+) {
+    for (effects, mut sprite) in query.iter_mut() {
+        // Keep the alpha, it's used for flashing while invulnerable
+        let alpha = sprite.color.a();
+        sprite.color = effects.tint().with_a(alpha);
+    }
+}
+
+fn clear_status_effects_on_death(
+    mut query: Query<(&mut StatusEffects, Option<&mut Character>)>,
+    mut death_events: EventReader<DeathEvent>,
+) {
+    for ev in death_events.read() {
+        let Ok((mut effects, character)) = query.get_mut(ev.entity) else {
+            continue;
+        };
+        effects.clear();
+        if let Some(mut character) = character {
+            character.speed_multiplier = 1.0;
+        }
+    }
+}
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApplyStatusEffect>().add_systems(
+            Update,
+            (
+                handle_apply_status_effects,
+                tick_status_effects,
+                clear_status_effects_on_death,
+                tint_status_effects,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
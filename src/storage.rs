@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Loads whatever was last saved under `key`, or the default if there's nothing usable there
+pub fn load<T: DeserializeOwned + Default>(key: &str) -> T {
+    let Some(text) = read(key) else {
+        return T::default();
+    };
+    match ron::from_str(&text) {
+        Ok(value) => value,
+        Err(err) => {
+            warn!("Couldn't read saved {key}, using defaults instead: {err}");
+            T::default()
+        }
+    }
+}
+
+/// Saves `value` so it can be loaded again next time the game starts.
+///
+/// Native builds write one RON file per key to the platform's config directory, the wasm build
+/// uses the browser's localStorage instead.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(text) => write(key, &text),
+        Err(err) => warn!("Couldn't save {key}: {err}"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> Option<std::path::PathBuf> {
+    let dirs = directories::ProjectDirs::from("", "", "night_shift")?;
+    Some(dirs.config_dir().join(format!("{key}.ron")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, text: &str) {
+    let Some(path) = path(key) else {
+        warn!("Couldn't find anywhere to save {key}");
+        return;
+    };
+    if let Some(dir) = path.parent() {
+        if let Err(err) = std::fs::create_dir_all(dir) {
+            warn!("Couldn't create {}: {err}", dir.display());
+            return;
+        }
+    }
+    if let Err(err) = std::fs::write(&path, text) {
+        warn!("Couldn't save {key} to {}: {err}", path.display());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?
+        .get_item(&format!("night_shift.{key}"))
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, text: &str) {
+    let Some(storage) = local_storage() else {
+        warn!("localStorage isn't available, can't save {key}");
+        return;
+    };
+    if storage
+        .set_item(&format!("night_shift.{key}"), text)
+        .is_err()
+    {
+        warn!("Couldn't save {key} to localStorage");
+    }
+}
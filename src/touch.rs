@@ -0,0 +1,256 @@
+use bevy::{input::touch::Touch, prelude::*, utils::HashMap, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContexts, EguiSettings};
+
+use crate::{
+    input::{Action, ActionState, UpdateActions},
+    localization::Localization,
+    states::{AppState, GameState},
+};
+
+/// How far a virtual stick can be pushed from where the touch started, in logical pixels
+const STICK_RADIUS: f32 = 70.0;
+const BUTTON_RADIUS: f32 = 45.0;
+const LINE_WIDTH: f32 = 3.0;
+/// Touches shorter than this that don't move far count as taps
+const TAP_TIME: f32 = 0.3;
+const TAP_DISTANCE: f32 = 20.0;
+
+/// A joystick that appears wherever a touch starts and follows the finger from there
+#[derive(Debug, Clone, Copy)]
+struct VirtualStick {
+    touch: u64,
+    center: Vec2,
+    position: Vec2,
+}
+
+impl VirtualStick {
+    fn new(touch: &Touch) -> Self {
+        VirtualStick {
+            touch: touch.id(),
+            center: touch.position(),
+            position: touch.position(),
+        }
+    }
+
+    /// How far the stick is pushed, with y pointing up like the world does
+    fn value(&self) -> Vec2 {
+        let offset = (self.position - self.center) / STICK_RADIUS;
+        Vec2::new(offset.x, -offset.y).clamp_length_max(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TouchButton {
+    Dash,
+    Pause,
+}
+
+impl TouchButton {
+    const ALL: [TouchButton; 2] = [TouchButton::Dash, TouchButton::Pause];
+
+    /// Where the button is, in logical pixels from the top left of the window
+    fn center(&self, window_size: Vec2) -> Vec2 {
+        match self {
+            // Just above where the right thumb rests for aiming
+            TouchButton::Dash => Vec2::new(window_size.x - 90.0, window_size.y - 240.0),
+            TouchButton::Pause => Vec2::new(60.0, 60.0),
+        }
+    }
+
+    fn action(&self) -> Action {
+        match self {
+            TouchButton::Dash => Action::Dash,
+            TouchButton::Pause => Action::Pause,
+        }
+    }
+
+    fn label<'a>(&self, localization: &'a Localization) -> &'a str {
+        match self {
+            TouchButton::Dash => localization.get("action.dash"),
+            TouchButton::Pause => "II",
+        }
+    }
+}
+
+/// Sent when the screen is tapped outside of gameplay, anywhere that isn't on a menu.
+///
+/// Menus with a single obvious way forward use this to carry on, the same way `Action::Confirm`
+/// does on screens without any buttons.
+#[derive(Event, Debug)]
+pub struct MenuTap;
+
+/// On-screen controls for playing without a keyboard.
+///
+/// The left half of the screen moves, the right half aims and fires. Outside of gameplay a tap
+/// counts as `Action::Confirm` and sends `MenuTap`.
+#[derive(Debug, Default, Resource)]
+pub struct TouchControls {
+    /// Turned on by the first touch, so the controls stay out of the way on desktop
+    pub enabled: bool,
+    move_stick: Option<VirtualStick>,
+    aim_stick: Option<VirtualStick>,
+    /// When each touch outside of gameplay started, for telling taps apart from drags. Touches
+    /// from during gameplay aren't included, so letting go of the pause button doesn't unpause.
+    started: HashMap<u64, f32>,
+}
+
+fn update_touch_controls(
+    mut controls: ResMut<TouchControls>,
+    mut actions: ResMut<ActionState>,
+    touches: Res<Touches>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    app_state: Res<State<AppState>>,
+    game_state: Res<State<GameState>>,
+    mut egui_contexts: EguiContexts,
+    mut tap_writer: EventWriter<MenuTap>,
+    time: Res<Time<Real>>,
+) {
+    if touches.any_just_pressed() {
+        controls.enabled = true;
+    }
+    if !controls.enabled {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+    let now = time.elapsed_seconds();
+    let playing = *app_state.get() == AppState::InGame && *game_state.get() == GameState::Playing;
+
+    for touch in touches.iter_just_pressed() {
+        if !playing {
+            controls.started.insert(touch.id(), now);
+            continue;
+        }
+
+        if let Some(button) = TouchButton::ALL
+            .into_iter()
+            .find(|b| b.center(window_size).distance(touch.position()) <= BUTTON_RADIUS)
+        {
+            actions.tap(button.action());
+        } else if touch.position().x < window_size.x / 2.0 {
+            if controls.move_stick.is_none() {
+                controls.move_stick = Some(VirtualStick::new(touch));
+            }
+        } else if controls.aim_stick.is_none() {
+            controls.aim_stick = Some(VirtualStick::new(touch));
+        }
+    }
+
+    let controls = &mut *controls;
+    for touch in touches.iter() {
+        for stick in [&mut controls.move_stick, &mut controls.aim_stick]
+            .into_iter()
+            .flatten()
+        {
+            if stick.touch == touch.id() {
+                stick.position = touch.position();
+            }
+        }
+    }
+
+    let over_menu = egui_contexts.ctx_mut().is_pointer_over_area();
+    for touch in touches
+        .iter_just_released()
+        .chain(touches.iter_just_canceled())
+    {
+        let started = controls.started.remove(&touch.id());
+        if controls.move_stick.is_some_and(|s| s.touch == touch.id()) {
+            controls.move_stick = None;
+        }
+        if controls.aim_stick.is_some_and(|s| s.touch == touch.id()) {
+            controls.aim_stick = None;
+        }
+
+        let tapped = started.is_some_and(|started| now - started <= TAP_TIME)
+            && touch.distance().length() <= TAP_DISTANCE;
+        if !playing && tapped && !over_menu {
+            actions.tap(Action::Confirm);
+            tap_writer.send(MenuTap);
+        }
+    }
+
+    if !playing {
+        controls.move_stick = None;
+        controls.aim_stick = None;
+        return;
+    }
+
+    if let Some(stick) = controls.move_stick {
+        actions.add_movement(stick.value());
+    }
+    if let Some(stick) = controls.aim_stick {
+        actions.aim_with_stick(stick.value());
+        actions.press(Action::Fire);
+    }
+}
+
+fn draw_touch_controls(
+    controls: Res<TouchControls>,
+    mut egui_contexts: EguiContexts,
+    egui_settings: Res<EguiSettings>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    localization: Res<Localization>,
+) {
+    if !controls.enabled {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let window_size = Vec2::new(window.width(), window.height());
+
+    // egui works in points, which are logical pixels divided by its own scale
+    let scale = egui_settings.scale_factor as f32;
+    let to_egui = |position: Vec2| egui::pos2(position.x / scale, position.y / scale);
+
+    let color = egui::Color32::from_white_alpha(60);
+    let painter = egui_contexts
+        .ctx_mut()
+        .layer_painter(egui::LayerId::background());
+
+    for stick in [controls.move_stick, controls.aim_stick]
+        .into_iter()
+        .flatten()
+    {
+        let knob = stick.center + (stick.position - stick.center).clamp_length_max(STICK_RADIUS);
+        painter.circle_stroke(
+            to_egui(stick.center),
+            STICK_RADIUS / scale,
+            egui::Stroke::new(LINE_WIDTH, color),
+        );
+        painter.circle_filled(to_egui(knob), STICK_RADIUS / scale / 2.0, color);
+    }
+
+    for button in TouchButton::ALL {
+        let center = to_egui(button.center(window_size));
+        painter.circle_stroke(
+            center,
+            BUTTON_RADIUS / scale,
+            egui::Stroke::new(LINE_WIDTH, color),
+        );
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            button.label(&localization),
+            egui::FontId::proportional(24.0),
+            color,
+        );
+    }
+}
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuTap>()
+            .init_resource::<TouchControls>()
+            .add_systems(PreUpdate, update_touch_controls.after(UpdateActions))
+            .add_systems(
+                Update,
+                draw_touch_controls
+                    .run_if(in_state(AppState::InGame).and_then(in_state(GameState::Playing))),
+            );
+    }
+}
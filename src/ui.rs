@@ -1,15 +1,73 @@
-use bevy::prelude::*;
+use std::ops::RangeInclusive;
+
+use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_egui::{
     egui::{Rounding, Stroke, Visuals, WidgetText},
     *,
 };
 
+use crate::{
+    accessibility::AccessibilitySettings,
+    audio::{ChannelVolume, VolumeSettings},
+    localization::Localization,
+};
+
+/// The size menus and text are laid out for
+const DESIGN_RESOLUTION: Vec2 = Vec2::new(1280.0, 720.0);
+/// Don't shrink things any further than this, or text gets unreadable
+const MIN_UI_SCALE: f32 = 0.5;
+
 pub struct UiPlugin;
 
 pub fn square_button(text: impl Into<WidgetText>) -> egui::Button<'static> {
     egui::Button::new(text).rounding(Rounding::ZERO)
 }
 
+fn formatter(x: f64, _decimal_places: RangeInclusive<usize>) -> String {
+    format!("{x:.02}")
+}
+
+/// A slider and mute toggle for every mixer channel. Returns `true` if anything changed.
+pub fn volume_controls(
+    ui: &mut egui::Ui,
+    volume_settings: &mut VolumeSettings,
+    localization: &Localization,
+) -> bool {
+    let mut changed = false;
+    let mut channel = |ui: &mut egui::Ui, name: &str, channel: &mut ChannelVolume| {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut channel.volume, 0.0..=2.0)
+                        .text(name)
+                        .custom_formatter(formatter),
+                )
+                .changed();
+            changed |= ui
+                .checkbox(&mut channel.muted, localization.get("volume.mute"))
+                .changed();
+        });
+    };
+
+    channel(
+        ui,
+        localization.get("volume.master"),
+        &mut volume_settings.master,
+    );
+    channel(
+        ui,
+        localization.get("volume.music"),
+        &mut volume_settings.music,
+    );
+    channel(ui, localization.get("volume.sfx"), &mut volume_settings.sfx);
+    channel(
+        ui,
+        localization.get("volume.interface"),
+        &mut volume_settings.ui,
+    );
+    changed
+}
+
 fn load_ui_assets() {}
 
 fn setup_egui(mut contexts: EguiContexts) {
@@ -29,9 +87,38 @@ fn setup_egui(mut contexts: EguiContexts) {
     })
 }
 
+/// Shrinks egui and Bevy UI on windows smaller than `DESIGN_RESOLUTION`, mostly for phones, then
+/// applies the UI scale from the accessibility settings on top
+fn scale_ui_to_window(
+    window_query: Query<Ref<Window>, With<PrimaryWindow>>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut ui_scale: ResMut<UiScale>,
+    accessibility: Res<AccessibilitySettings>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    if !window.is_changed() && !accessibility.is_changed() {
+        return;
+    }
+    let fit = (window.width() / DESIGN_RESOLUTION.x)
+        .min(window.height() / DESIGN_RESOLUTION.y)
+        .clamp(MIN_UI_SCALE, 1.0);
+    let scale = (fit * accessibility.ui_scale) as f64;
+
+    // The window changes whenever the cursor moves, so only touch these when the scale does
+    if egui_settings.scale_factor != scale {
+        egui_settings.scale_factor = scale;
+    }
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(EguiPlugin)
-            .add_systems(Startup, (load_ui_assets, setup_egui));
+            .add_systems(Startup, (load_ui_assets, setup_egui))
+            .add_systems(Update, scale_ui_to_window);
     }
 }